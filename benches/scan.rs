@@ -0,0 +1,79 @@
+//! Compares reading a large directory tree on one thread with reading it on several.
+//!
+//! Run with `cargo bench --bench scan`, optionally followed by `-- <path>` to scan an existing
+//! tree instead of a generated one. The generated tree has 50,000 files in 2,500 directories.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use simple_tree::DirTree;
+
+const RUNS: usize = 5;
+
+/// Creates a tree of `width` directories, each with `width` subdirectories holding `files` files.
+fn generate(root: &Path, width: usize, files: usize) {
+    for i in 0..width {
+        for j in 0..width {
+            let dir = root.join(format!("dir{}/sub{}", i, j));
+            fs::create_dir_all(&dir).unwrap();
+            for k in 0..files {
+                fs::write(dir.join(format!("file{}", k)), "").unwrap();
+            }
+        }
+    }
+}
+
+/// Returns the fastest of several times taken to read the tree at the given path on the given
+/// number of threads, along with the rendered tree.
+fn time(root: &Path, threads: usize) -> (Duration, String) {
+    let mut best = Duration::MAX;
+    let mut rendered = String::new();
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let tree = DirTree::builder(root).threads(threads).build().unwrap();
+        best = best.min(start.elapsed());
+        rendered = tree.to_string();
+    }
+    (best, rendered)
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to the benchmark.
+    let given = env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let (root, generated) = match given {
+        Some(path) => (PathBuf::from(path), false),
+        None => {
+            let root = env::temp_dir().join("simple-tree-bench-scan");
+            if !root.exists() {
+                generate(&root, 50, 20);
+            }
+            (root, true)
+        }
+    };
+
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    let (serial, expected) = time(&root, 1);
+    println!("{} lines in {}", expected.lines().count(), root.display());
+    println!("threads   1: {:>10.2?}", serial);
+    let mut counts = vec![2, 4, 8, cpus];
+    counts.retain(|&threads| threads > 1);
+    counts.sort_unstable();
+    counts.dedup();
+    for threads in counts {
+        let (parallel, rendered) = time(&root, threads);
+        assert_eq!(rendered, expected, "trees differ with {} threads", threads);
+        println!(
+            "threads {:>3}: {:>10.2?} ({:.2}x)",
+            threads,
+            parallel,
+            serial.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+
+    if generated {
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+//! Ordered tree edit distance between any two [Node] trees, using the Zhang–Shasha algorithm.
+//!
+//! The distance is the cheapest sequence of node deletions, insertions and relabelings which turns
+//! one tree into the other, where deleting a node moves its children up to its parent, and
+//! inserting a node adopts a consecutive run of siblings as its children. Unlike a diff of the
+//! rendered text, moving a subtree costs only the nodes which actually change.
+
+use std::fmt;
+
+use crate::node::Node;
+
+/// [EditCosts] defines the cost of each kind of edit operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditCosts {
+    /// The cost of inserting a node into the source tree.
+    pub insert: usize,
+    /// The cost of deleting a node from the source tree.
+    pub delete: usize,
+    /// The cost of changing the value of a node. Nodes whose values are equal are matched for free.
+    pub relabel: usize,
+}
+
+impl Default for EditCosts {
+    /// Every operation costs `1`.
+    fn default() -> Self {
+        EditCosts {
+            insert: 1,
+            delete: 1,
+            relabel: 1,
+        }
+    }
+}
+
+/// A single step of an [EditScript].
+///
+/// Nodes are identified by their index in a pre-order traversal of their tree, so the root of each
+/// tree is `0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// The node in the source tree is kept as the node in the target tree, unchanged.
+    Match {
+        from: usize,
+        to: usize,
+        value: String,
+    },
+    /// The node in the source tree is kept as the node in the target tree, with a new value.
+    Relabel {
+        from: usize,
+        to: usize,
+        old: String,
+        new: String,
+    },
+    /// The node in the source tree is deleted.
+    Delete { from: usize, value: String },
+    /// The node in the target tree is inserted.
+    Insert { to: usize, value: String },
+}
+
+/// The result of comparing two trees: their edit distance and a cheapest sequence of edits which
+/// achieves it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditScript {
+    /// The total cost of the edits.
+    pub distance: usize,
+    /// Every edit, including matches, ordered by the position of the node in the source tree and
+    /// then by the position of the node in the target tree.
+    pub edits: Vec<Edit>,
+}
+
+impl EditScript {
+    /// Returns an iterator over the edits which change the tree, skipping matches.
+    pub fn changes(&self) -> impl Iterator<Item = &Edit> {
+        self.edits
+            .iter()
+            .filter(|e| !matches!(e, Edit::Match { .. }))
+    }
+}
+
+impl fmt::Display for EditScript {
+    /// Writes the distance followed by one line per change, where `-` marks a deletion, `+` an
+    /// insertion and `~` a relabeling. Node indices are shown in brackets.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "distance {}", self.distance)?;
+        for edit in self.changes() {
+            match edit {
+                Edit::Delete { from, value } => write!(f, "\n- [{}] {}", from, value)?,
+                Edit::Insert { to, value } => write!(f, "\n+ [{}] {}", to, value)?,
+                Edit::Relabel { from, to, old, new } => {
+                    write!(f, "\n~ [{}] {} -> [{}] {}", from, old, to, new)?
+                }
+                Edit::Match { .. } => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A tree flattened into post-order, as used by the Zhang–Shasha algorithm.
+struct Flat {
+    values: Vec<String>,
+    // The post-order index of the leftmost leaf descendent of each node.
+    leftmost: Vec<usize>,
+    // The pre-order index of each node.
+    preorder: Vec<usize>,
+    // The nodes which are the highest with their leftmost leaf, in increasing order.
+    keyroots: Vec<usize>,
+}
+
+impl Flat {
+    fn new<N: Node>(root: &N) -> Self {
+        let mut flat = Flat {
+            values: Vec::new(),
+            leftmost: Vec::new(),
+            preorder: Vec::new(),
+            keyroots: Vec::new(),
+        };
+        let mut next_preorder = 0;
+        flat.push(root, &mut next_preorder);
+        let mut highest = vec![None; flat.values.len()];
+        for (i, &l) in flat.leftmost.iter().enumerate() {
+            highest[l] = Some(i);
+        }
+        flat.keyroots = highest.into_iter().flatten().collect();
+        flat.keyroots.sort_unstable();
+        flat
+    }
+
+    /// Appends the subtree rooted at the given node in post-order and returns the index of the
+    /// node.
+    fn push<N: Node>(&mut self, node: &N, next_preorder: &mut usize) -> usize {
+        let preorder = *next_preorder;
+        *next_preorder += 1;
+        let mut leftmost = None;
+        for child in node.children() {
+            let child_index = self.push(child, next_preorder);
+            leftmost.get_or_insert(self.leftmost[child_index]);
+        }
+        let index = self.values.len();
+        self.values.push(node.value().to_string());
+        self.leftmost.push(leftmost.unwrap_or(index));
+        self.preorder.push(preorder);
+        index
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// The state of a single comparison.
+struct ZhangShasha {
+    a: Flat,
+    b: Flat,
+    costs: EditCosts,
+    // The distance between every pair of subtrees.
+    tree_dist: Vec<Vec<usize>>,
+}
+
+impl ZhangShasha {
+    /// Flattens both trees and computes the distance between every pair of their subtrees.
+    fn new<A: Node, B: Node>(a: &A, b: &B, costs: EditCosts) -> Self {
+        let (a, b) = (Flat::new(a), Flat::new(b));
+        let tree_dist = vec![vec![0; b.len()]; a.len()];
+        let mut zs = ZhangShasha {
+            a,
+            b,
+            costs,
+            tree_dist,
+        };
+        for i in zs.a.keyroots.clone() {
+            for j in zs.b.keyroots.clone() {
+                zs.forest_dist(i, j);
+            }
+        }
+        zs
+    }
+
+    /// Returns the post-order indices of the roots of both trees.
+    fn roots(&self) -> (usize, usize) {
+        (self.a.len() - 1, self.b.len() - 1)
+    }
+
+    fn relabel_cost(&self, x: usize, y: usize) -> usize {
+        if self.a.values[x] == self.b.values[y] {
+            0
+        } else {
+            self.costs.relabel
+        }
+    }
+
+    /// Computes the distance between every prefix of the forest of the subtree rooted at `i` and
+    /// every prefix of that of the subtree rooted at `j`, recording the distance between whole
+    /// subtrees in `tree_dist` along the way.
+    ///
+    /// Row `x - leftmost(i) + 1` and column `y - leftmost(j) + 1` hold the distance between the
+    /// forests up to and including `x` and `y`, and row and column `0` are the empty forests.
+    fn forest_dist(&mut self, i: usize, j: usize) -> Vec<Vec<usize>> {
+        let (li, lj) = (self.a.leftmost[i], self.b.leftmost[j]);
+        let (rows, cols) = (i - li + 2, j - lj + 2);
+        let mut fd = vec![vec![0; cols]; rows];
+        for x in 1..rows {
+            fd[x][0] = fd[x - 1][0] + self.costs.delete;
+        }
+        for y in 1..cols {
+            fd[0][y] = fd[0][y - 1] + self.costs.insert;
+        }
+        for x in 1..rows {
+            for y in 1..cols {
+                let (ax, by) = (x + li - 1, y + lj - 1);
+                let delete = fd[x - 1][y] + self.costs.delete;
+                let insert = fd[x][y - 1] + self.costs.insert;
+                if self.a.leftmost[ax] == li && self.b.leftmost[by] == lj {
+                    let relabel = fd[x - 1][y - 1] + self.relabel_cost(ax, by);
+                    fd[x][y] = delete.min(insert).min(relabel);
+                    self.tree_dist[ax][by] = fd[x][y];
+                } else {
+                    let (px, py) = (self.a.leftmost[ax] - li, self.b.leftmost[by] - lj);
+                    let subtree = fd[px][py] + self.tree_dist[ax][by];
+                    fd[x][y] = delete.min(insert).min(subtree);
+                }
+            }
+        }
+        fd
+    }
+
+    /// Returns the edits which map the subtree rooted at `i` onto the subtree rooted at `j`,
+    /// assuming `tree_dist` is complete.
+    fn backtrack(&mut self, i: usize, j: usize, edits: &mut Vec<(usize, usize, Edit)>) {
+        let mut pending = vec![(i, j)];
+        while let Some((i, j)) = pending.pop() {
+            let fd = self.forest_dist(i, j);
+            let (li, lj) = (self.a.leftmost[i], self.b.leftmost[j]);
+            let (mut x, mut y) = (i - li + 1, j - lj + 1);
+            while x > 0 || y > 0 {
+                let (ax, by) = ((x + li).wrapping_sub(1), (y + lj).wrapping_sub(1));
+                if x > 0 && fd[x][y] == fd[x - 1][y] + self.costs.delete {
+                    edits.push(self.delete(ax));
+                    x -= 1;
+                } else if y > 0 && fd[x][y] == fd[x][y - 1] + self.costs.insert {
+                    edits.push(self.insert(by));
+                    y -= 1;
+                } else if self.a.leftmost[ax] == li && self.b.leftmost[by] == lj {
+                    edits.push(self.keep(ax, by));
+                    x -= 1;
+                    y -= 1;
+                } else {
+                    pending.push((ax, by));
+                    x = self.a.leftmost[ax] - li;
+                    y = self.b.leftmost[by] - lj;
+                }
+            }
+        }
+    }
+
+    fn delete(&self, x: usize) -> (usize, usize, Edit) {
+        let from = self.a.preorder[x];
+        let value = self.a.values[x].clone();
+        (from, 0, Edit::Delete { from, value })
+    }
+
+    fn insert(&self, y: usize) -> (usize, usize, Edit) {
+        let to = self.b.preorder[y];
+        let value = self.b.values[y].clone();
+        // Sort insertions after every edit of the source tree.
+        (usize::MAX, to, Edit::Insert { to, value })
+    }
+
+    fn keep(&self, x: usize, y: usize) -> (usize, usize, Edit) {
+        let (from, to) = (self.a.preorder[x], self.b.preorder[y]);
+        let edit = if self.a.values[x] == self.b.values[y] {
+            Edit::Match {
+                from,
+                to,
+                value: self.a.values[x].clone(),
+            }
+        } else {
+            Edit::Relabel {
+                from,
+                to,
+                old: self.a.values[x].clone(),
+                new: self.b.values[y].clone(),
+            }
+        };
+        (from, to, edit)
+    }
+}
+
+/// Returns the edit distance between the trees rooted at `a` and `b` under the given costs.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::edit_distance::{EditCosts, edit_distance};
+/// use simple_tree::implementations::NodeBinaryUnbalanced;
+///
+/// let mut a = NodeBinaryUnbalanced::new(7);
+/// a.insert(3);
+/// a.insert(13);
+/// let mut b = NodeBinaryUnbalanced::new(7);
+/// b.insert(3);
+/// b.insert(5);
+///
+/// assert_eq!(edit_distance(&a, &a, EditCosts::default()), 0);
+/// // Add 5 under 3 and delete 13.
+/// assert_eq!(edit_distance(&a, &b, EditCosts::default()), 2);
+/// // Relabelling 13 to 5 cannot help, since 5 sits below 3 rather than beside it.
+/// let costs = EditCosts { insert: 5, delete: 5, relabel: 1 };
+/// assert_eq!(edit_distance(&a, &b, costs), 10);
+/// ```
+pub fn edit_distance<A: Node, B: Node>(a: &A, b: &B, costs: EditCosts) -> usize {
+    let zs = ZhangShasha::new(a, b, costs);
+    let (root_a, root_b) = zs.roots();
+    zs.tree_dist[root_a][root_b]
+}
+
+/// Returns the edit distance between the trees rooted at `a` and `b` under the given costs, along
+/// with the edits which achieve it.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::edit_distance::{Edit, EditCosts, edit_script};
+/// use simple_tree::implementations::Trie;
+///
+/// let a = Trie::from(vec!["ab", "b"]);
+/// let b = Trie::from(vec!["ab", "c"]);
+///
+/// let script = edit_script(&a, &b, EditCosts::default());
+/// assert_eq!(script.distance, 1);
+/// assert_eq!(format!("{}", script), "distance 1\n~ [3] b 1 -> [3] c 1");
+/// assert_eq!(script.edits.len(), 4);
+/// assert!(matches!(script.edits[0], Edit::Match { from: 0, to: 0, .. }));
+/// ```
+pub fn edit_script<A: Node, B: Node>(a: &A, b: &B, costs: EditCosts) -> EditScript {
+    let mut zs = ZhangShasha::new(a, b, costs);
+    let (root_a, root_b) = zs.roots();
+    let mut edits = Vec::new();
+    zs.backtrack(root_a, root_b, &mut edits);
+    edits.sort_by_key(|&(from, to, _)| (from, to));
+    EditScript {
+        distance: zs.tree_dist[root_a][root_b],
+        edits: edits.into_iter().map(|(_, _, edit)| edit).collect(),
+    }
+}
@@ -0,0 +1,685 @@
+//! An interactive view of any [Node] tree, in which nodes can be expanded and collapsed, searched
+//! incrementally, and navigated with the arrow keys.
+//!
+//! The [Explorer] holds the state of the view and knows nothing about terminals. Input and output
+//! go through a [Backend], so the same explorer can be driven by a real terminal
+//! ([TerminalBackend]) or by a scripted list of keys ([ScriptedBackend]).
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+
+use crate::node::{BLANK, BRANCH, LAST_BRANCH, Node, PIPE};
+
+/// The marker drawn before a node whose children are hidden.
+const COLLAPSED: &str = "▸ ";
+/// The marker drawn before a node whose children are shown.
+const EXPANDED: &str = "▾ ";
+
+/// A key press understood by the [Explorer].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Esc,
+    Backspace,
+    Char(char),
+    /// Ctrl-C, which quits even while typing a search query.
+    Interrupt,
+}
+
+/// A single screen of output produced by the [Explorer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The rendered tree lines which fit on the screen.
+    pub lines: Vec<String>,
+    /// The index into `lines` of the selected node, if it is on screen.
+    pub cursor: Option<usize>,
+    /// The status line shown below the tree, such as the current search query.
+    pub status: String,
+}
+
+impl fmt::Display for Frame {
+    /// Writes each line of the frame, marking the selected line with `>`, followed by the status
+    /// line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            let marker = if self.cursor == Some(i) { "> " } else { "  " };
+            writeln!(f, "{}{}", marker, line)?;
+        }
+        write!(f, "{}", self.status)
+    }
+}
+
+/// [Backend] abstracts over where the [Explorer] reads keys from and draws frames to.
+pub trait Backend {
+    /// Returns the width and height of the drawable area, in characters.
+    fn size(&mut self) -> io::Result<(usize, usize)>;
+
+    /// Draws the given frame, replacing whatever was previously drawn.
+    fn draw(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// Blocks until the next key is pressed and returns it, or returns `None` if there is no more
+    /// input.
+    fn next_key(&mut self) -> io::Result<Option<Key>>;
+}
+
+/// A headless [Backend] which replays a fixed list of keys and records every frame drawn.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::explorer::{Explorer, Key, ScriptedBackend};
+/// use simple_tree::implementations::NodeBinaryUnbalanced;
+///
+/// let mut root = NodeBinaryUnbalanced::new(7);
+/// root.insert(3);
+/// root.insert(13);
+///
+/// let mut backend = ScriptedBackend::new(80, 10, vec![Key::Down, Key::Char('q')]);
+/// Explorer::new(&root).run(&mut backend).unwrap();
+///
+/// assert_eq!(backend.frames().len(), 2);
+/// assert_eq!(backend.frames()[1].cursor, Some(1));
+/// ```
+#[derive(Debug)]
+pub struct ScriptedBackend {
+    width: usize,
+    height: usize,
+    keys: VecDeque<Key>,
+    frames: Vec<Frame>,
+}
+
+impl ScriptedBackend {
+    /// Returns a new [ScriptedBackend] with a screen of the given size which will yield the given
+    /// keys in order.
+    pub fn new(width: usize, height: usize, keys: impl IntoIterator<Item = Key>) -> Self {
+        ScriptedBackend {
+            width,
+            height,
+            keys: keys.into_iter().collect(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Returns every frame drawn so far, oldest first.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns the most recently drawn frame, if any.
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.frames.last()
+    }
+}
+
+impl Backend for ScriptedBackend {
+    fn size(&mut self) -> io::Result<(usize, usize)> {
+        Ok((self.width, self.height))
+    }
+
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        self.frames.push(frame.clone());
+        Ok(())
+    }
+
+    fn next_key(&mut self) -> io::Result<Option<Key>> {
+        Ok(self.keys.pop_front())
+    }
+}
+
+/// The interactive state of a single node, flattened out of the original tree.
+#[derive(Debug)]
+struct Entry {
+    label: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    expanded: bool,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Browse,
+    /// An incremental search is being typed. `origin` is the node selected when the search began,
+    /// which is restored if the search is cancelled.
+    Search {
+        query: String,
+        origin: usize,
+    },
+}
+
+/// The state of an interactive tree view.
+///
+/// The tree is read once when the [Explorer] is created, so the original nodes need not outlive
+/// it. Only the root is expanded initially.
+///
+/// | Key                  | Action                                                         |
+/// |----------------------|----------------------------------------------------------------|
+/// | `Up`/`k`, `Down`/`j` | Move the selection                                             |
+/// | `Right`/`l`          | Expand the selected node, or move to its first child           |
+/// | `Left`/`h`           | Collapse the selected node, or jump to its parent              |
+/// | `p`                  | Jump to the parent of the selected node                        |
+/// | `Enter`/space        | Toggle the selected node                                       |
+/// | `/`                  | Start an incremental search; `Enter` accepts, `Esc` cancels    |
+/// | `n`, `N`             | Jump to the next or previous match of the last search          |
+/// | `Home`, `End`        | Jump to the first or last visible node                         |
+/// | `PageUp`, `PageDown` | Move the selection by a screen                                 |
+/// | `q`/`Esc`            | Quit                                                           |
+#[derive(Debug)]
+pub struct Explorer {
+    // Entries in pre-order, so index 0 is the root and a search can scan them in display order.
+    entries: Vec<Entry>,
+    cursor: usize,
+    scroll: usize,
+    mode: Mode,
+    last_query: String,
+}
+
+impl Explorer {
+    /// Returns a new [Explorer] over the tree rooted at the given node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::explorer::Explorer;
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(7);
+    /// root.insert(3);
+    /// root.insert(5);
+    /// root.insert(13);
+    ///
+    /// let explorer = Explorer::new(&root);
+    /// assert_eq!(explorer.selected(), "7");
+    /// assert_eq!(explorer.lines(), vec!["▾ 7", "├── ▸ 3", "└── 13"]);
+    /// ```
+    pub fn new<N: Node>(root: &N) -> Self {
+        let mut entries = Vec::new();
+        Self::flatten(root, None, &mut entries);
+        entries[0].expanded = true;
+        Explorer {
+            entries,
+            cursor: 0,
+            scroll: 0,
+            mode: Mode::Browse,
+            last_query: String::new(),
+        }
+    }
+
+    /// Appends the given node and its descendents to `entries` in pre-order, returning the index
+    /// of the node.
+    fn flatten<N: Node>(node: &N, parent: Option<usize>, entries: &mut Vec<Entry>) -> usize {
+        let index = entries.len();
+        entries.push(Entry {
+            label: node.value().to_string(),
+            parent,
+            children: Vec::new(),
+            expanded: false,
+        });
+        for child in node.children() {
+            let child_index = Self::flatten(child, Some(index), entries);
+            entries[index].children.push(child_index);
+        }
+        index
+    }
+
+    /// Returns the displayed value of the selected node.
+    pub fn selected(&self) -> &str {
+        &self.entries[self.cursor].label
+    }
+
+    /// Returns the indices of the entries which are currently visible, in display order.
+    fn visible(&self) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            visible.push(index);
+            let entry = &self.entries[index];
+            if entry.expanded {
+                stack.extend(entry.children.iter().rev());
+            }
+        }
+        visible
+    }
+
+    /// Returns whether the given entry is the last child of its parent.
+    fn is_last(&self, index: usize) -> bool {
+        match self.entries[index].parent {
+            Some(parent) => self.entries[parent].children.last() == Some(&index),
+            None => true,
+        }
+    }
+
+    /// Renders the given entry as a single line, with the same branches as [Node::fmt] and a
+    /// marker showing whether a node with children is expanded.
+    fn render_line(&self, index: usize) -> String {
+        let mut prefixes = Vec::new();
+        let mut current = index;
+        while let Some(parent) = self.entries[current].parent {
+            let is_last = self.is_last(current);
+            prefixes.push(match (current == index, is_last) {
+                (true, false) => BRANCH,
+                (true, true) => LAST_BRANCH,
+                (false, false) => PIPE,
+                (false, true) => BLANK,
+            });
+            current = parent;
+        }
+        let mut line: String = prefixes.into_iter().rev().collect();
+        let entry = &self.entries[index];
+        if !entry.children.is_empty() {
+            line.push_str(if entry.expanded { EXPANDED } else { COLLAPSED });
+        }
+        line.push_str(&entry.label);
+        line
+    }
+
+    /// Returns every visible line of the tree, ignoring the size of the screen.
+    pub fn lines(&self) -> Vec<String> {
+        self.visible()
+            .into_iter()
+            .map(|i| self.render_line(i))
+            .collect()
+    }
+
+    /// Renders the part of the tree which fits in a screen of the given size, scrolling as needed
+    /// to keep the selection on screen. One row is reserved for the status line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::explorer::{Explorer, Key};
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(7);
+    /// root.insert(3);
+    /// root.insert(5);
+    /// root.insert(13);
+    ///
+    /// let mut explorer = Explorer::new(&root);
+    /// explorer.handle_key(Key::Down, 10);
+    /// explorer.handle_key(Key::Right, 10);
+    /// explorer.handle_key(Key::Right, 10);
+    ///
+    /// assert_eq!(format!("{}", explorer.render(80, 3)),
+    /// "  ├── ▾ 3
+    /// > │   └── 5
+    /// ");
+    ///
+    /// explorer.handle_key(Key::Left, 10);
+    /// explorer.handle_key(Key::Left, 10);
+    /// assert_eq!(format!("{}", explorer.render(80, 10)),
+    /// "  ▾ 7
+    /// > ├── ▸ 3
+    ///   └── 13
+    /// ");
+    /// ```
+    pub fn render(&mut self, width: usize, height: usize) -> Frame {
+        let rows = height.saturating_sub(1).max(1);
+        let visible = self.visible();
+        let position = visible.iter().position(|&i| i == self.cursor).unwrap_or(0);
+        // Never leave rows empty at the bottom while lines are scrolled off the top.
+        self.scroll = self.scroll.min(visible.len().saturating_sub(rows));
+        if position < self.scroll {
+            self.scroll = position;
+        } else if position >= self.scroll + rows {
+            self.scroll = position + 1 - rows;
+        }
+        let lines = visible
+            .iter()
+            .skip(self.scroll)
+            .take(rows)
+            .map(|&i| self.render_line(i).chars().take(width).collect())
+            .collect();
+        let status = match &self.mode {
+            Mode::Browse => String::new(),
+            Mode::Search { query, .. } => format!("/{}", query),
+        };
+        Frame {
+            lines,
+            cursor: Some(position - self.scroll),
+            status: status.chars().take(width).collect(),
+        }
+    }
+
+    /// Draws the explorer with the given backend and handles keys until the user quits or the
+    /// backend runs out of input.
+    pub fn run<B: Backend>(&mut self, backend: &mut B) -> io::Result<()> {
+        loop {
+            let (width, height) = backend.size()?;
+            backend.draw(&self.render(width, height))?;
+            let Some(key) = backend.next_key()? else {
+                return Ok(());
+            };
+            let (_, height) = backend.size()?;
+            if !self.handle_key(key, height.saturating_sub(1).max(1)) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Updates the state of the explorer in response to the given key, where `page` is the number
+    /// of rows moved by `PageUp` and `PageDown`. Returns `false` if the key asks to quit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::explorer::{Explorer, Key};
+    /// use simple_tree::implementations::Trie;
+    ///
+    /// let trie = Trie::from(vec!["hey", "hi", "hello"]);
+    /// let mut explorer = Explorer::new(&trie);
+    ///
+    /// for key in [Key::Char('/'), Key::Char('h'), Key::Char('e'), Key::Char('l')] {
+    ///     explorer.handle_key(key, 10);
+    /// }
+    /// explorer.handle_key(Key::Enter, 10);
+    /// assert_eq!(explorer.selected(), "hel 0");
+    ///
+    /// explorer.handle_key(Key::Char('p'), 10);
+    /// assert_eq!(explorer.selected(), "he 0");
+    ///
+    /// assert!(!explorer.handle_key(Key::Char('q'), 10));
+    ///
+    /// explorer.handle_key(Key::Char('/'), 10);
+    /// assert!(explorer.handle_key(Key::Char('q'), 10));
+    /// assert!(!explorer.handle_key(Key::Interrupt, 10));
+    /// ```
+    pub fn handle_key(&mut self, key: Key, page: usize) -> bool {
+        if key == Key::Interrupt {
+            return false;
+        }
+        if let Mode::Search { query, origin } = &mut self.mode {
+            let origin = *origin;
+            match key {
+                Key::Enter => {
+                    self.last_query = std::mem::take(query);
+                    self.mode = Mode::Browse;
+                }
+                Key::Esc => {
+                    self.mode = Mode::Browse;
+                    self.cursor = origin;
+                }
+                Key::Backspace => {
+                    query.pop();
+                    let query = query.clone();
+                    self.search_from(origin, &query, true);
+                }
+                Key::Char(c) => {
+                    query.push(c);
+                    let query = query.clone();
+                    self.search_from(origin, &query, true);
+                }
+                _ => (),
+            }
+            return true;
+        }
+        match key {
+            Key::Char('q') | Key::Esc => return false,
+            Key::Up | Key::Char('k') => self.move_by(-1),
+            Key::Down | Key::Char('j') => self.move_by(1),
+            Key::PageUp => self.move_by(-(page as isize)),
+            Key::PageDown => self.move_by(page as isize),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = *self.visible().last().unwrap(),
+            Key::Right | Key::Char('l') => {
+                let entry = &mut self.entries[self.cursor];
+                if entry.expanded {
+                    if let Some(&first) = entry.children.first() {
+                        self.cursor = first;
+                    }
+                } else {
+                    entry.expanded = true;
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                let entry = &mut self.entries[self.cursor];
+                if entry.expanded && !entry.children.is_empty() {
+                    entry.expanded = false;
+                } else if let Some(parent) = entry.parent {
+                    self.cursor = parent;
+                }
+            }
+            Key::Char('p') | Key::Backspace => {
+                if let Some(parent) = self.entries[self.cursor].parent {
+                    self.cursor = parent;
+                }
+            }
+            Key::Enter | Key::Char(' ') => {
+                let entry = &mut self.entries[self.cursor];
+                entry.expanded = !entry.expanded;
+            }
+            Key::Char('/') => {
+                self.mode = Mode::Search {
+                    query: String::new(),
+                    origin: self.cursor,
+                }
+            }
+            Key::Char('n') => {
+                let query = self.last_query.clone();
+                self.search_from(self.cursor + 1, &query, true);
+            }
+            Key::Char('N') => {
+                let query = self.last_query.clone();
+                let start = self.cursor.checked_sub(1).unwrap_or(self.entries.len() - 1);
+                self.search_from(start, &query, false);
+            }
+            _ => (),
+        }
+        true
+    }
+
+    /// Moves the selection by the given number of visible lines, stopping at either end.
+    fn move_by(&mut self, delta: isize) {
+        let visible = self.visible();
+        let position = visible.iter().position(|&i| i == self.cursor).unwrap_or(0);
+        let target = position.saturating_add_signed(delta).min(visible.len() - 1);
+        self.cursor = visible[target];
+    }
+
+    /// Selects the first node at or after (or before, if not `forward`) the given entry whose
+    /// label contains `query`, wrapping around the tree, and expands its ancestors so it is
+    /// visible. Leaves the selection unchanged if nothing matches.
+    fn search_from(&mut self, start: usize, query: &str, forward: bool) {
+        if query.is_empty() {
+            return;
+        }
+        let len = self.entries.len();
+        let found = (0..len)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset) % len
+                }
+            })
+            .find(|&i| self.entries[i].label.contains(query));
+        if let Some(index) = found {
+            self.cursor = index;
+            let mut current = self.entries[index].parent;
+            while let Some(parent) = current {
+                self.entries[parent].expanded = true;
+                current = self.entries[parent].parent;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use terminal::TerminalBackend;
+
+#[cfg(unix)]
+mod terminal {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::process::{Command, Stdio};
+
+    use super::{Backend, Frame, Key};
+
+    /// A [Backend] which draws to the controlling terminal, using `stty` to switch it into raw
+    /// mode for as long as the backend is alive.
+    #[derive(Debug)]
+    pub struct TerminalBackend {
+        tty: File,
+        saved_mode: String,
+        pending: Vec<u8>,
+    }
+
+    impl TerminalBackend {
+        /// Opens the controlling terminal, switches it to raw mode and the alternate screen, and
+        /// returns a new [TerminalBackend] which restores both when dropped.
+        ///
+        /// # Errors
+        ///
+        /// This function will error if there is no controlling terminal or `stty` fails.
+        pub fn new() -> io::Result<Self> {
+            let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+            let saved_mode = stty(&tty, &["-g"])?;
+            // Reads return after at most a tenth of a second, so a lone escape can be told apart
+            // from the start of an escape sequence.
+            stty(&tty, &["raw", "-echo", "min", "0", "time", "1"])?;
+            let mut backend = TerminalBackend {
+                tty,
+                saved_mode: saved_mode.trim().to_string(),
+                pending: Vec::new(),
+            };
+            backend.tty.write_all(b"\x1b[?1049h\x1b[?25l")?;
+            Ok(backend)
+        }
+
+        /// Reads at least one byte into the pending buffer, waiting as long as it takes.
+        fn fill(&mut self) -> io::Result<()> {
+            let mut buf = [0; 64];
+            loop {
+                let n = self.tty.read(&mut buf)?;
+                if n > 0 {
+                    self.pending.extend_from_slice(&buf[..n]);
+                    return Ok(());
+                }
+            }
+        }
+
+        /// Reads whatever bytes arrive before the terminal's read timeout expires.
+        fn fill_nowait(&mut self) -> io::Result<()> {
+            let mut buf = [0; 64];
+            let n = self.tty.read(&mut buf)?;
+            self.pending.extend_from_slice(&buf[..n]);
+            Ok(())
+        }
+    }
+
+    impl Backend for TerminalBackend {
+        fn size(&mut self) -> io::Result<(usize, usize)> {
+            let size = stty(&self.tty, &["size"])?;
+            let mut parts = size.split_whitespace().map(|s| s.parse::<usize>());
+            match (parts.next(), parts.next()) {
+                (Some(Ok(rows)), Some(Ok(cols))) => Ok((cols, rows)),
+                _ => Ok((80, 24)),
+            }
+        }
+
+        fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+            let mut out = String::from("\x1b[H\x1b[2J");
+            for (i, line) in frame.lines.iter().enumerate() {
+                if frame.cursor == Some(i) {
+                    out.push_str(&format!("\x1b[7m{}\x1b[0m\r\n", line));
+                } else {
+                    out.push_str(&format!("{}\r\n", line));
+                }
+            }
+            out.push_str(&frame.status);
+            self.tty.write_all(out.as_bytes())?;
+            self.tty.flush()
+        }
+
+        fn next_key(&mut self) -> io::Result<Option<Key>> {
+            loop {
+                if self.pending.is_empty() {
+                    self.fill()?;
+                }
+                if self.pending[0] == 0x1b && self.pending.len() < 3 {
+                    self.fill_nowait()?;
+                }
+                let (key, used) = parse_key(&self.pending);
+                if used == 0 {
+                    // The key is incomplete, so wait for the rest of it.
+                    self.fill()?;
+                    continue;
+                }
+                self.pending.drain(..used);
+                if let Some(key) = key {
+                    return Ok(Some(key));
+                }
+            }
+        }
+    }
+
+    impl Drop for TerminalBackend {
+        fn drop(&mut self) {
+            let _ = self.tty.write_all(b"\x1b[?25h\x1b[?1049l");
+            let _ = stty(&self.tty, &[self.saved_mode.as_str()]);
+        }
+    }
+
+    /// Runs `stty` with the given arguments on the given terminal and returns its output.
+    fn stty(tty: &File, args: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::from(tty.try_clone()?))
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other("stty failed"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Parses the key at the start of the given input, returning the key, if it was recognised,
+    /// and the number of bytes it occupied.
+    fn parse_key(input: &[u8]) -> (Option<Key>, usize) {
+        match input {
+            [0x1b, b'[', b'A', ..] => (Some(Key::Up), 3),
+            [0x1b, b'[', b'B', ..] => (Some(Key::Down), 3),
+            [0x1b, b'[', b'C', ..] => (Some(Key::Right), 3),
+            [0x1b, b'[', b'D', ..] => (Some(Key::Left), 3),
+            [0x1b, b'[', b'H', ..] => (Some(Key::Home), 3),
+            [0x1b, b'[', b'F', ..] => (Some(Key::End), 3),
+            [0x1b, b'[', b'5', b'~', ..] => (Some(Key::PageUp), 4),
+            [0x1b, b'[', b'6', b'~', ..] => (Some(Key::PageDown), 4),
+            [0x1b, b'[', rest @ ..] => {
+                // Skip any other escape sequence up to and including its final byte.
+                let end = rest.iter().position(|b| (0x40..=0x7e).contains(b));
+                (None, end.map_or(input.len(), |e| e + 3))
+            }
+            [0x1b, ..] => (Some(Key::Esc), 1),
+            [b'\r' | b'\n', ..] => (Some(Key::Enter), 1),
+            [0x7f | 0x08, ..] => (Some(Key::Backspace), 1),
+            // Ctrl-C quits, as it would outside of raw mode.
+            [0x03, ..] => (Some(Key::Interrupt), 1),
+            [first, ..] => {
+                let width = match first {
+                    0xf0.. => 4,
+                    0xe0.. => 3,
+                    0xc0.. => 2,
+                    _ => 1,
+                };
+                if input.len() < width {
+                    return (None, 0);
+                }
+                let key = std::str::from_utf8(&input[..width])
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .map(Key::Char);
+                (key, width)
+            }
+            [] => (None, 0),
+        }
+    }
+}
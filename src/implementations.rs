@@ -3,5 +3,5 @@ pub mod dir_tree;
 pub mod trie;
 
 pub use binary_unbalanced::NodeBinaryUnbalanced;
-pub use dir_tree::DirTree;
+pub use dir_tree::{DirTree, DirTreeBuilder};
 pub use trie::Trie;
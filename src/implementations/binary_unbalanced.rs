@@ -3,6 +3,13 @@ use std::fmt;
 
 use crate::node::Node;
 
+/// The side of its parent on which a node was inserted.
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
 #[derive(Debug)]
 pub struct NodeBinaryUnbalanced<T>
 where
@@ -10,6 +17,7 @@ where
 {
     val: T,
     count: usize,
+    side: Option<Side>,
     left: Option<Box<NodeBinaryUnbalanced<T>>>,
     right: Option<Box<NodeBinaryUnbalanced<T>>>,
 }
@@ -35,6 +43,7 @@ where
         NodeBinaryUnbalanced {
             val: value,
             count: 1,
+            side: None,
             left: None,
             right: None,
         }
@@ -71,12 +80,17 @@ where
     ///     └── 15");
     /// ```
     pub fn insert(&mut self, value: T) {
+        let child = |value, side| {
+            let mut node = Self::new(value);
+            node.side = Some(side);
+            Some(Box::new(node))
+        };
         match value.cmp(&self.val) {
             Ordering::Less => {
                 if let Some(left) = &mut self.left {
                     left.insert(value);
                 } else {
-                    self.left = Some(Box::new(Self::new(value)));
+                    self.left = child(value, Side::Left);
                 }
             }
             Ordering::Equal => self.count += 1,
@@ -84,7 +98,7 @@ where
                 if let Some(right) = &mut self.right {
                     right.insert(value);
                 } else {
-                    self.right = Some(Box::new(Self::new(value)));
+                    self.right = child(value, Side::Right);
                 }
             }
         }
@@ -112,6 +126,38 @@ where
         &self.val
     }
 
+    /// Returns `L` if this node is the left child of its parent, `R` if it is the right child, or
+    /// `None` if it is the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::render::{EdgeLabelStyle, RenderOptions};
+    /// use simple_tree::Node;
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(7);
+    /// root.insert(3);
+    /// root.insert(5);
+    /// root.insert(13);
+    ///
+    /// let options = RenderOptions {
+    ///     edge_labels: EdgeLabelStyle::Inline,
+    ///     ..RenderOptions::default()
+    /// };
+    /// assert_eq!(format!("{}", root.render(options)),
+    /// "7
+    /// ├─[L]─ 3
+    /// │   └─[R]─ 5
+    /// └─[R]─ 13");
+    /// ```
+    fn edge_label(&self) -> Option<impl fmt::Display> {
+        self.side.map(|side| match side {
+            Side::Left => "L",
+            Side::Right => "R",
+        })
+    }
+
     /// Returns an iterator which yields the left child, if present, and then the right child, if
     /// present.
     ///
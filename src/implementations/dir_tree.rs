@@ -1,15 +1,50 @@
+pub mod colors;
+pub mod display;
+pub mod duplicates;
+pub mod glob;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+pub mod hash;
+pub mod ignore;
+pub mod metadata;
+pub mod mounts;
+pub mod paths;
+mod scan;
+pub mod snapshot;
+pub mod sort;
+mod stream;
+pub mod tar;
+pub mod watch;
+pub mod zip;
+
 use std::fmt;
-use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::node::Node;
+pub use display::{DEFAULT_TIME_FORMAT, DisplayOptions, SizeFormat};
+use glob::Glob;
+use hash::Digest;
+pub use metadata::{EntryMetadata, FileType};
+use mounts::MountTable;
+pub use scan::ScanError;
+pub use sort::SortOrder;
 
 #[derive(Debug)]
 enum SpecialFile {
     None,
     Root,
-    Symlink(PathBuf),
+    Symlink(PathBuf, LinkStatus),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkStatus {
+    Ok,
+    // The target of the link does not exist.
+    Broken,
+    // The link points to a directory which is already being read, so it was not followed.
+    Recursive,
 }
 
 #[derive(Debug)]
@@ -17,6 +52,36 @@ pub struct DirTree {
     path: PathBuf,
     special: SpecialFile,
     children: Vec<DirTree>,
+    // The entry is a directory, or a symbolic link to one which was followed.
+    is_dir: bool,
+    // The entry is a directory which was not read because it is below the maximum depth.
+    truncated: bool,
+    // A problem reading the entry, shown alongside it, when tolerating errors.
+    error: Option<&'static str>,
+    // The size of the entry itself, in bytes.
+    size: u64,
+    // The size of the entry and all of its descendents, in bytes.
+    total_size: u64,
+    // The compressed size of an entry of an archive, in bytes, if it is recorded.
+    compressed_size: Option<u64>,
+    metadata: EntryMetadata,
+    // The hash of the contents of a regular file, if read.
+    hash: Option<Digest>,
+    // The path relative to the root of the first entry in the tree which is a hard link to the
+    // same file, if this is a later one and hard links are being counted once, or of the entry
+    // an entry of an archive is stored as a hard link to.
+    hard_link: Option<PathBuf>,
+    // The type of the filesystem mounted at the entry, if it is a mount point and mount points
+    // are being annotated.
+    mount: Option<String>,
+    // Whether the entry was listed, such as by [DirTreeBuilder::from_paths], rather than read
+    // from the filesystem.
+    listed: bool,
+    // The path shown for the entry instead of its name, relative to its parent in the tree, for
+    // entries moved up by [DirTreeBuilder::files_only].
+    label: Option<PathBuf>,
+    // The display options are shared by every node in the tree.
+    display: Arc<DisplayOptions>,
 }
 
 impl DirTree {
@@ -83,33 +148,1026 @@ impl DirTree {
     where
         PathBuf: From<P>,
     {
-        let path = PathBuf::from(path);
-        Self::new_internal(path, true)
+        Self::builder(path).build()
     }
 
-    fn new_internal(path: PathBuf, is_root: bool) -> io::Result<Self> {
-        let metadata = path.symlink_metadata()?;
-        let special = match (is_root, metadata.is_symlink()) {
-            (true, _) => SpecialFile::Root,
-            (_, true) => SpecialFile::Symlink(path.read_link()?),
-            _ => SpecialFile::None,
-        };
-        let mut children = Vec::new();
-        // Only traverse symlinks if the path is the root of the dir tree.
-        if metadata.is_dir() || is_root && metadata.is_symlink() && path.metadata()?.is_dir() {
-            for entry in fs::read_dir(&path)? {
-                children.push(Self::new_internal(entry?.path(), false)?);
+    /// Returns a [DirTreeBuilder] which reads the directory tree starting at the given path, and
+    /// whose options can be changed before the tree is built.
+    ///
+    /// Without changing any options, the builder produces the same tree as [DirTree::new].
+    pub fn builder<P>(path: P) -> DirTreeBuilder
+    where
+        PathBuf: From<P>,
+    {
+        DirTreeBuilder {
+            root: PathBuf::from(path),
+            show_hidden: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            gitignore: false,
+            max_depth: None,
+            follow_links: false,
+            threads: 1,
+            sort: SortOrder::default(),
+            reverse: false,
+            dirs_first: false,
+            dirs_only: false,
+            files_only: false,
+            prune: false,
+            hash: false,
+            hard_links: false,
+            one_filesystem: false,
+            mounts: false,
+            mount_table: None,
+            separator: String::from("/"),
+            display: Arc::default(),
+        }
+    }
+
+    /// Returns the size of the entry associated with this node, in bytes. For symbolic links, this
+    /// is the size of the link itself rather than its target.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the total size of the entry associated with this node and everything below it in
+    /// the tree, in bytes, like `du`. Entries which were filtered out of the tree, or are below a
+    /// truncated directory, are not counted. If the tree was built with
+    /// [DirTreeBuilder::hard_links], later hard links to a file already in the tree are not
+    /// counted either, and have a total size of `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-total-size");
+    /// fs::create_dir_all(tmpdir.join("a")).unwrap();
+    /// fs::write(tmpdir.join("a/one"), [0; 100]).unwrap();
+    /// fs::write(tmpdir.join("a/two"), [0; 20]).unwrap();
+    ///
+    /// let root = DirTree::new(&tmpdir).unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let a = root.children().next().unwrap();
+    /// assert_eq!(a.total_size(), a.size() + 120);
+    /// assert_eq!(root.total_size(), root.size() + a.total_size());
+    /// ```
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Returns the metadata of the entry associated with this node. For symbolic links, this is
+    /// the metadata of the link itself rather than its target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions};
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-metadata");
+    /// fs::create_dir_all(&tmpdir).unwrap();
+    /// fs::write(tmpdir.join("file"), "").unwrap();
+    /// fs::set_permissions(tmpdir.join("file"), fs::Permissions::from_mode(0o640)).unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .display(DisplayOptions {
+    ///         permissions: true,
+    ///         links: true,
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let file = root.children().next().unwrap();
+    /// assert_eq!(file.metadata().mode & 0o7777, 0o640);
+    /// assert_eq!(format!("{}", file.value()), "[-rw-r-----   1]  file");
+    /// # }
+    /// ```
+    pub fn metadata(&self) -> &EntryMetadata {
+        &self.metadata
+    }
+
+    /// Returns the SHA-256 hash of the contents of the entry associated with this node, if it is a
+    /// non-empty regular file with the same size as another file in the tree, and the tree was
+    /// built with [DirTreeBuilder::hash].
+    pub fn hash(&self) -> Option<Digest> {
+        self.hash
+    }
+
+    /// Returns the path relative to the root of the first entry in the tree which is a hard link
+    /// to the same file as the entry associated with this node, if this node is a later one and
+    /// the tree was built with [DirTreeBuilder::hard_links], or of the entry this node is stored
+    /// as a hard link to, if it is an entry of an archive.
+    pub fn hard_link(&self) -> Option<&Path> {
+        self.hard_link.as_deref()
+    }
+
+    /// Returns the type of the filesystem mounted at the entry associated with this node, such as
+    /// `ext4` or `proc`, if it is a mount point and the tree was built with
+    /// [DirTreeBuilder::mounts]. The type is `?` if it is not known.
+    pub fn mount_type(&self) -> Option<&str> {
+        self.mount.as_deref()
+    }
+
+    /// Returns the compressed size of the entry associated with this node, in bytes, if it is an
+    /// entry of an archive which records it, such as one read by [DirTreeBuilder::from_zip].
+    pub fn compressed_size(&self) -> Option<u64> {
+        self.compressed_size
+    }
+
+    /// Sets the display options for this node and all of its descendents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions, SizeFormat};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-display-size");
+    /// fs::create_dir_all(tmpdir.join("a")).unwrap();
+    /// fs::write(tmpdir.join("a/big"), vec![0; 5000]).unwrap();
+    /// fs::write(tmpdir.join("small"), [0; 10]).unwrap();
+    ///
+    /// let mut root = DirTree::new(&tmpdir).unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// root.set_display_options(DisplayOptions {
+    ///     size: Some(SizeFormat::Bytes),
+    ///     ..DisplayOptions::default()
+    /// });
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(2).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "│   └── [       5000]  big",
+    ///     "└── [         10]  small",
+    /// ]);
+    ///
+    /// root.set_display_options(DisplayOptions {
+    ///     size: Some(SizeFormat::Iec),
+    ///     du: true,
+    ///     ..DisplayOptions::default()
+    /// });
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(2).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "│   └── [4.9K]  big",
+    ///     "└── [  10]  small",
+    /// ]);
+    /// ```
+    pub fn set_display_options(&mut self, display: DisplayOptions) {
+        self.share_display_options(Arc::new(display));
+    }
+
+    fn share_display_options(&mut self, display: Arc<DisplayOptions>) {
+        self.children
+            .iter_mut()
+            .for_each(|c| c.share_display_options(Arc::clone(&display)));
+        self.display = display;
+    }
+
+    /// Returns whether this node is a directory whose contents were not read because it is at the
+    /// maximum depth set by [DirTreeBuilder::max_depth].
+    ///
+    /// Truncated directories are displayed with a trailing `…`, to tell them apart from empty
+    /// directories.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns a description of the problem encountered while reading the entry associated with
+    /// this node, such as `error opening dir`, if any. This is only ever set for trees built by
+    /// [DirTreeBuilder::build_tolerant], where it is displayed in brackets after the name.
+    pub fn error(&self) -> Option<&'static str> {
+        self.error
+    }
+
+    /// Returns the type of the entry associated with this node. For symbolic links, this is
+    /// [FileType::Symlink] rather than the type of their target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use std::os::unix::fs::{PermissionsExt, symlink};
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions, FileType};
+    /// use simple_tree::implementations::dir_tree::colors::LsColors;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-file-type");
+    /// fs::create_dir_all(tmpdir.join("dir")).unwrap();
+    /// fs::write(tmpdir.join("run.sh"), "").unwrap();
+    /// fs::set_permissions(tmpdir.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    /// symlink("dir", tmpdir.join("link")).unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .display(DisplayOptions {
+    ///         classify: true,
+    ///         colors: Some(LsColors::parse("di=01;34:ex=01;32")),
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let types: Vec<FileType> = root.children().map(|c| c.file_type()).collect();
+    /// assert_eq!(types, [FileType::Dir, FileType::Symlink, FileType::File]);
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── \x1b[01;34mdir\x1b[0m/",
+    ///     "├── link@ -> dir",
+    ///     "└── \x1b[01;32mrun.sh\x1b[0m*",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn file_type(&self) -> FileType {
+        self.metadata.file_type()
+    }
+
+    /// Returns whether this node is a directory, or a symbolic link to a directory which is
+    /// followed (see [DirTreeBuilder::follow_links]).
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns whether this node is a symbolic link whose target does not exist. Broken links are
+    /// displayed with a trailing `[broken link]`. Unless links are followed (see
+    /// [DirTreeBuilder::follow_links]), only the target itself is checked, not any link it leads
+    /// to. A followed link whose target exists but cannot be read, such as one in a loop of links,
+    /// is not broken, but is displayed with `[error reading link target]` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use std::os::unix::fs::symlink;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-broken-link");
+    /// fs::create_dir_all(&tmpdir).unwrap();
+    /// symlink("nowhere", tmpdir.join("missing")).unwrap();
+    /// symlink("loop", tmpdir.join("loop")).unwrap();
+    ///
+    /// let root = DirTree::new(&tmpdir).unwrap();
+    /// let followed = DirTree::builder(&tmpdir).follow_links(true).build().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let (looped, missing) = (root.children().next().unwrap(), root.children().nth(1).unwrap());
+    /// assert!(missing.is_broken_link());
+    /// assert!(!looped.is_broken_link());
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, ["├── loop -> loop", "└── missing -> nowhere  [broken link]"]);
+    ///
+    /// let lines: Vec<String> = format!("{}", followed).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── loop -> loop  [error reading link target]",
+    ///     "└── missing -> nowhere  [broken link]",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn is_broken_link(&self) -> bool {
+        matches!(self.special, SpecialFile::Symlink(_, LinkStatus::Broken))
+    }
+
+    /// Returns whether this node is a symbolic link to a directory which was not followed because
+    /// the directory contains the link (see [DirTreeBuilder::follow_links]).
+    pub fn is_recursive_link(&self) -> bool {
+        matches!(self.special, SpecialFile::Symlink(_, LinkStatus::Recursive))
+    }
+}
+
+/// [DirTreeBuilder] configures how the filesystem is read when building a [DirTree].
+///
+/// Filters are applied while the filesystem is being read, so directories which are filtered out
+/// are never read at all. The root itself is never filtered out.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+/// use std::fs;
+/// use simple_tree::implementations::DirTree;
+///
+/// let tmpdir = env::temp_dir().join("test-dir-tree-builder");
+/// fs::create_dir_all(tmpdir.join(".git/objects")).unwrap();
+/// fs::create_dir_all(tmpdir.join("src/bin")).unwrap();
+/// fs::create_dir_all(tmpdir.join("target/debug")).unwrap();
+/// fs::write(tmpdir.join(".gitignore"), "target").unwrap();
+/// fs::write(tmpdir.join("src/lib.rs"), "").unwrap();
+/// fs::write(tmpdir.join("src/bin/main.rs"), "").unwrap();
+/// fs::write(tmpdir.join("src/notes.txt"), "").unwrap();
+///
+/// let root = DirTree::builder(&tmpdir)
+///     .show_hidden(false)
+///     .include("*.rs")
+///     .exclude("target")
+///     .build()
+///     .unwrap();
+///
+/// fs::remove_dir_all(&tmpdir).unwrap();
+///
+/// assert_eq!(format!("{}", root), format!(
+/// "{}
+/// └── src
+///     ├── bin
+///     │   └── main.rs
+///     └── lib.rs", tmpdir.display()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirTreeBuilder {
+    root: PathBuf,
+    show_hidden: bool,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    gitignore: bool,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    threads: usize,
+    sort: SortOrder,
+    reverse: bool,
+    dirs_first: bool,
+    dirs_only: bool,
+    files_only: bool,
+    prune: bool,
+    hash: bool,
+    hard_links: bool,
+    one_filesystem: bool,
+    mounts: bool,
+    mount_table: Option<Arc<MountTable>>,
+    separator: String,
+    display: Arc<DisplayOptions>,
+}
+
+impl DirTreeBuilder {
+    /// Sets whether entries whose names begin with `.` are included, like `tree -a`. Hidden
+    /// entries are included by default.
+    pub fn show_hidden(mut self, show_hidden: bool) -> Self {
+        self.show_hidden = show_hidden;
+        self
+    }
+
+    /// Adds a [Glob] pattern which files must match to be included, like `tree -P`. If no include
+    /// patterns are added, every file is included. Directories are included regardless, so that
+    /// matching files within them can be found.
+    ///
+    /// Patterns without a `/` are matched against the name of each entry, and patterns with a `/`
+    /// are matched against the path of each entry relative to the root, using `/` as the
+    /// separator.
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(Glob::new(pattern));
+        self
+    }
+
+    /// Adds a [Glob] pattern for entries to leave out, like `tree -I`. Excluded directories are
+    /// not read at all. Patterns are matched as for [Self::include].
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(Glob::new(pattern));
+        self
+    }
+
+    /// Sets whether entries ignored by `.gitignore`, `.ignore` and `.git/info/exclude` files are
+    /// left out, using the same rules as git (see [IgnoreFile](ignore::IgnoreFile)). Ignored
+    /// directories are not read at all. This is off by default.
+    ///
+    /// `.gitignore` and `.ignore` files are read from every directory in the tree. If the root is
+    /// inside a git repository, the ignore files of the directories between the top of the
+    /// repository and the root apply too, as does the repository's `.git/info/exclude`. Rules in
+    /// deeper ignore files take precedence over those in their parents, and within a directory,
+    /// `.ignore` takes precedence over `.gitignore`. `.git/info/exclude` has the lowest
+    /// precedence of all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-gitignore");
+    /// fs::create_dir_all(tmpdir.join("target/debug")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("web/node_modules/left-pad")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("web/dist")).unwrap();
+    /// fs::create_dir_all(tmpdir.join(".git/info")).unwrap();
+    /// fs::write(tmpdir.join(".git/info/exclude"), "*.tmp\n").unwrap();
+    /// fs::write(tmpdir.join("web/notes.tmp"), "").unwrap();
+    /// fs::write(tmpdir.join(".gitignore"), "target/\n*.log\n").unwrap();
+    /// fs::write(tmpdir.join("web/.gitignore"), "node_modules/\n!important.log\n/dist\n").unwrap();
+    /// fs::write(tmpdir.join("build.log"), "").unwrap();
+    /// fs::write(tmpdir.join("web/debug.log"), "").unwrap();
+    /// fs::write(tmpdir.join("web/important.log"), "").unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .show_hidden(false)
+    ///     .gitignore(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// assert_eq!(format!("{}", root), format!(
+    /// "{}
+    /// └── web
+    ///     └── important.log", tmpdir.display()));
+    /// ```
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Sets the maximum depth of the tree, like `tree -L`, where the children of the root are at
+    /// depth `1`. Directories at the maximum depth are not read at all, and are flagged as
+    /// truncated (see [DirTree::is_truncated]). By default, there is no maximum depth.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-max-depth");
+    /// fs::create_dir_all(tmpdir.join("a/b/c")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("d")).unwrap();
+    /// fs::write(tmpdir.join("a/file"), "").unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir).max_depth(Some(2)).build().unwrap();
+    ///
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// assert_eq!(format!("{}", root), format!(
+    /// "{}
+    /// ├── a
+    /// │   ├── b …
+    /// │   └── file
+    /// └── d", tmpdir.display()));
+    ///
+    /// let a = root.children().next().unwrap();
+    /// assert!(!a.is_truncated());
+    /// assert!(a.children().next().unwrap().is_truncated());
+    /// ```
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets whether only directories are included, like `tree -d`. This is off by default.
+    pub fn dirs_only(mut self, dirs_only: bool) -> Self {
+        self.dirs_only = dirs_only;
+        self
+    }
+
+    /// Sets whether only the entries other than directories are shown, as children of the root
+    /// named by their paths relative to it, in the order they would otherwise appear. Directories
+    /// are still read, but are left out of the tree, so this also leaves out the contents of
+    /// directories which are not read, such as those at the maximum depth. This is off by
+    /// default. With this set, [Self::stream] builds the whole tree in memory before writing
+    /// anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use std::path::MAIN_SEPARATOR;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-files-only");
+    /// fs::create_dir_all(tmpdir.join("a/b")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("c")).unwrap();
+    /// fs::write(tmpdir.join("a/b/one"), "").unwrap();
+    /// fs::write(tmpdir.join("a/two"), "").unwrap();
+    /// fs::write(tmpdir.join("three"), "").unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir).files_only(true).build().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     format!("├── a{0}b{0}one", MAIN_SEPARATOR),
+    ///     format!("├── a{0}two", MAIN_SEPARATOR),
+    ///     String::from("└── three"),
+    /// ]);
+    /// ```
+    pub fn files_only(mut self, files_only: bool) -> Self {
+        self.files_only = files_only;
+        self
+    }
+
+    /// Sets whether directories which are empty once every other filter has been applied are left
+    /// out, like `tree --prune`. Directories which only contain such directories are left out in
+    /// turn. Directories whose contents are unknown, because they are at the maximum depth or
+    /// could not be read, are kept. This is off by default. [Self::stream] cannot tell whether
+    /// a directory is left out until everything below it is read, so with this set it builds the
+    /// whole tree in memory first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-prune");
+    /// fs::create_dir_all(tmpdir.join("docs/drafts")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("src/empty")).unwrap();
+    /// fs::write(tmpdir.join("docs/drafts/notes.txt"), "").unwrap();
+    /// fs::write(tmpdir.join("src/main.rs"), "").unwrap();
+    ///
+    /// let tree = |dirs_only, prune| {
+    ///     let root = DirTree::builder(&tmpdir)
+    ///         .include("*.rs")
+    ///         .dirs_only(dirs_only)
+    ///         .prune(prune)
+    ///         .build()
+    ///         .unwrap();
+    ///     format!("{}", root).lines().skip(1).map(String::from).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(tree(false, true), ["└── src", "    └── main.rs"]);
+    /// assert_eq!(tree(true, false), [
+    ///     "├── docs",
+    ///     "│   └── drafts",
+    ///     "└── src",
+    ///     "    └── empty",
+    /// ]);
+    ///
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    /// ```
+    pub fn prune(mut self, prune: bool) -> Self {
+        self.prune = prune;
+        self
+    }
+
+    /// Sets whether directories on other filesystems than the root are left unread, like `tree -x`
+    /// or `du -x`, so that scanning `/` does not wander into `/proc` or network mounts. Such
+    /// directories are still shown, but appear to be empty. Directories are on other filesystems
+    /// if their device differs from that of the root, so bind mounts of the same filesystem are
+    /// still read. This is off by default, and has no effect on platforms other than unix.
+    pub fn one_filesystem(mut self, one_filesystem: bool) -> Self {
+        self.one_filesystem = one_filesystem;
+        self
+    }
+
+    /// Sets whether mount points are annotated with the type of the filesystem mounted there,
+    /// such as `[mount: proc]` (see [DirTree::mount_type]). This is off by default.
+    ///
+    /// Mount points are read from `/proc/self/mountinfo` (see [MountTable]), unless they are
+    /// given by [Self::mount_table], so bind mounts are found too. Where it cannot be read,
+    /// directories on a different device from their parent are taken to be mount points of an
+    /// unknown type, shown as `?`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::mounts::MountTable;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-mounts");
+    /// fs::create_dir_all(tmpdir.join("backup/2024")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("home")).unwrap();
+    ///
+    /// // Mount points are given by their absolute paths, with spaces escaped.
+    /// let backup = fs::canonicalize(&tmpdir).unwrap().join("backup");
+    /// let table = MountTable::parse(&format!(
+    ///     "40 22 0:35 / {} rw - nfs4 server:/export rw",
+    ///     backup.display().to_string().replace(' ', "\\040"),
+    /// ));
+    /// let root = DirTree::builder(&tmpdir).mount_table(table).build().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, ["├── backup  [mount: nfs4]", "│   └── 2024", "└── home"]);
+    /// assert_eq!(root.children().next().unwrap().mount_type(), Some("nfs4"));
+    /// ```
+    pub fn mounts(mut self, mounts: bool) -> Self {
+        self.mounts = mounts;
+        self
+    }
+
+    /// Sets the mounts used to annotate mount points, instead of reading them when the tree is
+    /// read, and turns on [Self::mounts].
+    pub fn mount_table(mut self, table: MountTable) -> Self {
+        self.mounts = true;
+        self.mount_table = Some(Arc::new(table));
+        self
+    }
+
+    /// Sets whether symbolic links to directories are followed, like `tree -l`, so that their
+    /// contents are read as if they were directories. By default, only a symbolic link at the
+    /// root is followed.
+    ///
+    /// A link to a directory which is already being read, such as a link to `..`, would lead to
+    /// an endless loop. Such links are not followed, and are displayed with a trailing
+    /// `[recursive, not followed]` instead (see [DirTree::is_recursive_link]). On unix,
+    /// directories are identified by their device and inode numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use std::os::unix::fs::symlink;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-follow-links");
+    /// fs::create_dir_all(tmpdir.join("a/b")).unwrap();
+    /// fs::write(tmpdir.join("a/b/file"), "").unwrap();
+    /// symlink("b", tmpdir.join("a/link")).unwrap();
+    /// symlink("..", tmpdir.join("a/b/up")).unwrap();
+    /// symlink("missing", tmpdir.join("dangling")).unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir).follow_links(true).build().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── a",
+    ///     "│   ├── b",
+    ///     "│   │   ├── file",
+    ///     "│   │   └── up -> ..  [recursive, not followed]",
+    ///     "│   └── link -> b",
+    ///     "│       ├── file",
+    ///     "│       └── up -> ..  [recursive, not followed]",
+    ///     "└── dangling -> missing  [broken link]",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Sets the number of threads used to read the filesystem, where `0` means one for each CPU.
+    /// By default, the filesystem is read on the current thread.
+    ///
+    /// With several threads, directories are read in parallel from a shared queue, and the tree
+    /// is assembled once they have all been read, so it is exactly the same as the tree read on
+    /// one thread. However, the errors returned by [Self::build_tolerant] may be in a different
+    /// order, and if several entries cannot be read, [Self::build] may return the error of any of
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-threads");
+    /// for i in 0..20 {
+    ///     let dir = tmpdir.join(format!("dir{}/sub{}", i % 4, i));
+    ///     fs::create_dir_all(&dir).unwrap();
+    ///     fs::write(dir.join("file"), vec![0; i]).unwrap();
+    /// }
+    ///
+    /// let serial = DirTree::builder(&tmpdir).build().unwrap();
+    /// let parallel = DirTree::builder(&tmpdir).threads(4).build().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// assert_eq!(format!("{}", parallel), format!("{}", serial));
+    /// assert_eq!(parallel.total_size(), serial.total_size());
+    /// ```
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the order of the children of each directory. By default, they are sorted by name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions, SizeFormat, SortOrder};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-sort-size");
+    /// fs::create_dir_all(tmpdir.join("a")).unwrap();
+    /// fs::write(tmpdir.join("a/big"), vec![0; 1 << 20]).unwrap();
+    /// fs::write(tmpdir.join("b"), vec![0; 1 << 19]).unwrap();
+    /// fs::write(tmpdir.join("c"), vec![0; 1 << 21]).unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .sort(SortOrder::Size)
+    ///     .display(DisplayOptions {
+    ///         size: Some(SizeFormat::Iec),
+    ///         du: true,
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── [2.0M]  c",
+    ///     "├── [1.0M]  a",
+    ///     "│   └── [1.0M]  big",
+    ///     "└── [512K]  b",
+    /// ]);
+    /// ```
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Sets whether the order set by [Self::sort] is reversed, like `tree -r`. This is off by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::dir_tree::{DirTree, SortOrder};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-sort-reverse");
+    /// fs::create_dir_all(tmpdir.join("dir1")).unwrap();
+    /// for name in ["file10", "file9", "File1.txt", "notes.md"] {
+    ///     fs::write(tmpdir.join(name), "").unwrap();
+    /// }
+    ///
+    /// let names = |order, reverse, dirs_first| {
+    ///     let root = DirTree::builder(&tmpdir)
+    ///         .sort(order)
+    ///         .reverse(reverse)
+    ///         .dirs_first(dirs_first)
+    ///         .build()
+    ///         .unwrap();
+    ///     format!("{}", root).lines().skip(1).map(|l| l[10..].to_string()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(
+    ///     names(SortOrder::Name, false, false),
+    ///     ["File1.txt", "dir1", "file10", "file9", "notes.md"],
+    /// );
+    /// assert_eq!(
+    ///     names(SortOrder::Natural, true, false),
+    ///     ["notes.md", "file10", "file9", "dir1", "File1.txt"],
+    /// );
+    /// assert_eq!(
+    ///     names(SortOrder::CaseInsensitive, false, true),
+    ///     ["dir1", "File1.txt", "file10", "file9", "notes.md"],
+    /// );
+    /// assert_eq!(
+    ///     names(SortOrder::Extension, false, false),
+    ///     ["dir1", "file10", "file9", "notes.md", "File1.txt"],
+    /// );
+    ///
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    /// ```
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Sets the separator between the components of the paths given to [Self::from_paths]. By
+    /// default, it is `/`.
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Sets whether directories are listed before all other entries, like `tree --dirsfirst`.
+    /// Directories and other entries are each sorted as set by [Self::sort] and [Self::reverse].
+    /// This is off by default.
+    pub fn dirs_first(mut self, dirs_first: bool) -> Self {
+        self.dirs_first = dirs_first;
+        self
+    }
+
+    /// Sets whether the contents of regular files are hashed with SHA-256 once the tree has been
+    /// read (see [DirTree::hash]). Files with a different size from every other file in the tree
+    /// cannot have the same contents as any of them, so they are not read, and neither are empty
+    /// files. Hashing still reads every other file, so it is off by default. With this set,
+    /// [Self::stream] builds the whole tree in memory first, to find the files with the same size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-hash");
+    /// fs::create_dir_all(tmpdir.join("dir")).unwrap();
+    /// fs::write(tmpdir.join("dir/file"), "hello world").unwrap();
+    /// fs::write(tmpdir.join("dir/other"), "hello there").unwrap();
+    /// // No other file has the same size, so this is not read.
+    /// fs::write(tmpdir.join("unique"), "hello").unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .hash(true)
+    ///     .display(DisplayOptions {
+    ///         hash: true,
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── [            ]  dir",
+    ///     "│   ├── [b94d27b9934d]  file",
+    ///     "│   └── [12998c017066]  other",
+    ///     "└── [            ]  unique",
+    /// ]);
+    /// ```
+    pub fn hash(mut self, hash: bool) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Sets whether files with several hard links are counted once, like `du`. The first entry in
+    /// the tree for each file, in the order the tree is shown, is counted as usual. Later entries
+    /// for the same file, identified by its device and inode numbers, are not counted in the
+    /// total sizes of the directories above them, are shown with a trailing `=> first/path` (see
+    /// [DirTree::hard_link]), and are left out of [DirTree::duplicates] as they take no extra
+    /// space. If the tree is sorted by size, entries are sorted again by their sizes without
+    /// later hard links. This is off by default, and has no effect on platforms other than unix.
+    /// Since whether an entry is counted depends on every entry before it, [Self::stream] builds
+    /// the whole tree in memory first when this is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::SortOrder;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-hard-links");
+    /// fs::create_dir_all(tmpdir.join("cache")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("project")).unwrap();
+    /// fs::write(tmpdir.join("cache/blob"), [0; 1000]).unwrap();
+    /// fs::hard_link(tmpdir.join("cache/blob"), tmpdir.join("project/lib.so")).unwrap();
+    /// fs::write(tmpdir.join("project/main.o"), [0; 10]).unwrap();
+    ///
+    /// let root = DirTree::builder(&tmpdir).hard_links(true).build().unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── cache",
+    ///     "│   └── blob",
+    ///     "└── project",
+    ///     "    ├── lib.so => cache/blob",
+    ///     "    └── main.o",
+    /// ]);
+    ///
+    /// let project = root.children().nth(1).unwrap();
+    /// assert_eq!(project.total_size(), project.size() + 10);
+    ///
+    /// // Sorted by size, the cache holds nothing the project does not, so it comes last.
+    /// fs::create_dir_all(tmpdir.join("docs")).unwrap();
+    /// fs::write(tmpdir.join("docs/guide.pdf"), [0; 500]).unwrap();
+    /// let root = DirTree::builder(&tmpdir)
+    ///     .hard_links(true)
+    ///     .sort(SortOrder::Size)
+    ///     .build()
+    ///     .unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let lines: Vec<String> = format!("{}", root).lines().skip(1).map(String::from).collect();
+    /// assert_eq!(lines, [
+    ///     "├── project",
+    ///     "│   ├── lib.so",
+    ///     "│   └── main.o",
+    ///     "├── docs",
+    ///     "│   └── guide.pdf",
+    ///     "└── cache",
+    ///     "    └── blob => project/lib.so",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn hard_links(mut self, hard_links: bool) -> Self {
+        self.hard_links = hard_links;
+        self
+    }
+
+    /// Sets the display options of the tree. See [DirTree::set_display_options].
+    pub fn display(mut self, display: DisplayOptions) -> Self {
+        self.display = Arc::new(display);
+        self
+    }
+
+    /// Reads the directory tree according to the builder's options and returns it.
+    ///
+    /// # Errors
+    ///
+    /// This function will error in the same cases as [DirTree::new].
+    pub fn build(&self) -> io::Result<DirTree> {
+        self.scan(false).map(|(tree, _)| tree)
+    }
+
+    /// Reads the directory tree according to the builder's options like [Self::build], but
+    /// continues past entries which cannot be read, returning the tree along with every error
+    /// encountered.
+    ///
+    /// Each entry with an error is kept in the tree, with a description of the problem shown in
+    /// brackets after its name like `tree` (see [DirTree::error]):
+    ///
+    /// - `error opening dir` or `error reading dir` if the contents of a directory could not be
+    ///   read, in which case the contents read so far are kept.
+    /// - `error reading entry` if the metadata of an entry could not be read, such as when it is
+    ///   removed during the scan. Such entries have a size of `0` and empty metadata.
+    /// - `error reading link` if the target of a symbolic link could not be read.
+    /// - `error hashing file` if the contents of a file could not be read while hashing them
+    ///   (see [Self::hash]).
+    ///
+    /// # Errors
+    ///
+    /// This function will error only if the root itself cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::env;
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    /// use simple_tree::implementations::DirTree;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-build-tolerant");
+    /// let locked = tmpdir.join("locked");
+    /// fs::create_dir_all(locked.join("secret")).unwrap();
+    /// fs::write(tmpdir.join("file"), "").unwrap();
+    /// fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    ///
+    /// // Privileged processes can read the directory regardless.
+    /// if fs::read_dir(&locked).is_err() {
+    ///     assert!(DirTree::builder(&tmpdir).build().is_err());
+    ///
+    ///     let (root, errors) = DirTree::builder(&tmpdir).build_tolerant().unwrap();
+    ///     assert_eq!(format!("{}", root), format!(
+    /// "{}
+    /// ├── file
+    /// └── locked  [error opening dir]", tmpdir.display()));
+    ///     assert_eq!(errors.len(), 1);
+    ///     assert_eq!(errors[0].path, locked);
+    /// }
+    ///
+    /// fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    /// # }
+    /// ```
+    pub fn build_tolerant(&self) -> io::Result<(DirTree, Vec<ScanError>)> {
+        self.scan(true)
+    }
+
+    /// Returns whether an entry with the given path relative to the root should be left out of
+    /// the tree.
+    fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        let name = relative
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if !self.show_hidden && name.starts_with('.') || self.dirs_only && !is_dir {
+            return true;
+        }
+        let relative = relative_str(relative);
+        let matches = |glob: &Glob| {
+            if glob.has_separator() {
+                glob.matches(&relative)
+            } else {
+                glob.matches(&name)
             }
+        };
+        if self.exclude.iter().any(matches) {
+            return true;
         }
-        children.sort_by(|a, b| a.path.cmp(&b.path));
-        Ok(DirTree {
-            path,
-            special,
-            children,
-        })
+        !is_dir && !self.include.is_empty() && !self.include.iter().any(matches)
     }
 }
 
+/// Returns the given relative path as a string with `/` separating its components, as expected
+/// by [Glob] patterns on every platform.
+fn relative_str(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 impl Node for DirTree {
     /// Returns a lossy string representation the path associated with this node.
     ///
@@ -197,22 +1255,98 @@ impl Node for DirTree {
     /// }
     /// ```
     fn value(&self) -> impl fmt::Display {
-        match &self.special {
-            SpecialFile::None => self
-                .path
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .into_owned(),
-            SpecialFile::Root => self.path.as_os_str().to_string_lossy().into_owned(),
-            SpecialFile::Symlink(target) => {
-                format!(
-                    "{} -> {}",
-                    self.path.file_name().unwrap().display(),
-                    target.as_os_str().display()
-                )
+        let display = &self.display;
+        let name = match (&self.special, &self.label) {
+            (SpecialFile::Root, _) => self.path.as_os_str().to_string_lossy(),
+            (_, Some(label)) => label.as_os_str().to_string_lossy(),
+            _ => self.path.file_name().unwrap().to_string_lossy(),
+        };
+        let mut name = match &display.colors {
+            Some(colors) => match colors.color(&name, &self.metadata, self.is_broken_link()) {
+                Some(color) => format!("\x1b[{}m{}\x1b[0m", color, name),
+                None => name.into_owned(),
+            },
+            None => name.into_owned(),
+        };
+        if display.classify && !matches!(self.special, SpecialFile::Root) {
+            name.push_str(match self.file_type() {
+                FileType::Dir => "/",
+                FileType::Symlink => "@",
+                FileType::Fifo => "|",
+                FileType::Socket => "=",
+                FileType::File if self.metadata.is_executable() => "*",
+                _ => "",
+            });
+        }
+        if let SpecialFile::Symlink(target, status) = &self.special {
+            let note = match status {
+                LinkStatus::Ok => "",
+                LinkStatus::Broken => "  [broken link]",
+                LinkStatus::Recursive => "  [recursive, not followed]",
+            };
+            name.push_str(&format!(" -> {}{}", target.as_os_str().display(), note));
+        }
+        if let Some(first) = &self.hard_link {
+            name.push_str(&format!(" => {}", relative_str(first)));
+        }
+        if let Some(fs_type) = &self.mount {
+            name.push_str(&format!("  [mount: {}]", fs_type));
+        }
+        let metadata = &self.metadata;
+        let mut columns = Vec::new();
+        if display.inode {
+            columns.push(format!("{:>9}", metadata.inode));
+        }
+        if display.permissions {
+            columns.push(metadata.mode_string());
+        }
+        if display.links {
+            columns.push(format!("{:>3}", metadata.links));
+        }
+        if display.user {
+            columns.push(format!("{:<8}", metadata::user_name(metadata.uid)));
+        }
+        if display.group {
+            columns.push(format!("{:<8}", metadata::group_name(metadata.gid)));
+        }
+        if let Some(format) = display.size {
+            let bytes = if display.du {
+                self.total_size
+            } else {
+                self.size
+            };
+            columns.push(format.format(bytes));
+            if display.compressed {
+                columns.push(match self.compressed_size {
+                    Some(bytes) => format.format(bytes),
+                    None => " ".repeat(format.format(0).len()),
+                });
             }
         }
+        if let Some(time_format) = &display.modified {
+            columns.push(match metadata.modified {
+                Some(time) => metadata::format_time(time, time_format),
+                None => String::from("?"),
+            });
+        }
+        if display.hash {
+            columns.push(match self.hash {
+                Some(hash) => hash.short(),
+                None => format!("{:12}", ""),
+            });
+        }
+        let mut line = String::new();
+        if !columns.is_empty() {
+            line.push_str(&format!("[{}]  ", columns.join(" ")));
+        }
+        line.push_str(&name);
+        if self.truncated {
+            line.push_str(" …");
+        }
+        if let Some(error) = self.error {
+            line.push_str(&format!("  [{}]", error));
+        }
+        line
     }
 
     /// If `self` is associated with a directory, returns an iterator over the path entries in that
@@ -222,7 +1356,8 @@ impl Node for DirTree {
     /// reads of the filesystem are made during this method call.
     ///
     /// Except for the root of the [DirTree], symbolic links are not traversed when constructing
-    /// the tree, so entries associated with symbolic links will return an empty iterator.
+    /// the tree unless [DirTreeBuilder::follow_links] is set, so entries associated with symbolic
+    /// links will otherwise return an empty iterator.
     ///
     /// # Examples
     ///
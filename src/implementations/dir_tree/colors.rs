@@ -0,0 +1,155 @@
+//! Coloring the names of entries like `ls`, according to the `LS_COLORS` environment variable.
+
+use std::env;
+
+use super::metadata::{EntryMetadata, FileType};
+
+/// The colors `ls` uses when `LS_COLORS` is not set.
+const DEFAULT_COLORS: &str = "rs=0:di=01;34:ln=01;36:mh=00:pi=40;33:so=01;35:do=01;35:\
+bd=40;33;01:cd=40;33;01:or=40;31;01:mi=00:su=37;41:sg=30;43:ca=00:tw=30;42:ow=34;42:st=37;44:\
+ex=01;32";
+
+/// [LsColors] holds the colors for each type of entry and for names with particular endings, in
+/// the format of the `LS_COLORS` environment variable used by GNU `ls`.
+///
+/// The format is a list of `key=color` entries separated by `:`, where each color is a list of
+/// SGR codes separated by `;`, such as `01;34` for bold blue. Keys are either a file type, or
+/// `*` followed by an ending such as `.tar` which applies to regular files whose names end with
+/// it. The file types understood are:
+///
+/// - `di` for directories, along with `tw`, `ow` and `st` for directories which are sticky and
+///   writable by others, writable by others, or sticky.
+/// - `fi` for regular files, along with `ex` for executables, `su` and `sg` for files which are
+///   setuid or setgid, and `mh` for files with several hard links.
+/// - `ln` for symbolic links, and `or` for broken ones.
+/// - `pi`, `so`, `bd` and `cd` for named pipes, sockets, and block and character devices.
+///
+/// As in `ls`, the more specific types take precedence, endings only apply to regular files with
+/// no more specific type, and a color of `0` or `00` is the same as no color. Endings are matched
+/// in the case given first, then ignoring case, and later entries take precedence. Other keys,
+/// including `ln=target`, are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::implementations::dir_tree::EntryMetadata;
+/// use simple_tree::implementations::dir_tree::colors::LsColors;
+///
+/// let colors = LsColors::parse("di=01;34:ex=01;32:*.tar=01;31:*.TAR=00;31");
+/// let metadata = |mode| EntryMetadata {
+///     mode,
+///     links: 1,
+///     ..EntryMetadata::default()
+/// };
+///
+/// assert_eq!(colors.color("src", &metadata(0o040755), false), Some("01;34"));
+/// assert_eq!(colors.color("run.sh", &metadata(0o100755), false), Some("01;32"));
+/// assert_eq!(colors.color("a.tar", &metadata(0o100644), false), Some("01;31"));
+/// assert_eq!(colors.color("B.TAR", &metadata(0o100644), false), Some("00;31"));
+/// assert_eq!(colors.color("c.Tar", &metadata(0o100644), false), Some("00;31"));
+/// assert_eq!(colors.color("notes", &metadata(0o100644), false), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsColors {
+    // The color for each file type key, such as `di`.
+    types: Vec<(String, String)>,
+    // The color for each name ending, in the order given.
+    endings: Vec<(String, String)>,
+}
+
+impl Default for LsColors {
+    /// Returns the colors `ls` uses when `LS_COLORS` is not set.
+    fn default() -> Self {
+        Self::parse(DEFAULT_COLORS)
+    }
+}
+
+impl LsColors {
+    /// Parses colors in the format of `LS_COLORS`, ignoring any malformed entries.
+    pub fn parse(s: &str) -> Self {
+        let mut colors = LsColors {
+            types: Vec::new(),
+            endings: Vec::new(),
+        };
+        for entry in s.split(':') {
+            let Some((key, color)) = entry.split_once('=') else {
+                continue;
+            };
+            match key.strip_prefix('*') {
+                Some(ending) => colors.endings.push((ending.to_string(), color.to_string())),
+                None => colors.types.push((key.to_string(), color.to_string())),
+            }
+        }
+        colors
+    }
+
+    /// Returns the colors given by the `LS_COLORS` environment variable, if it is set.
+    pub fn from_env() -> Option<Self> {
+        env::var("LS_COLORS").ok().map(|s| Self::parse(&s))
+    }
+
+    /// Returns the color of an entry with the given name and metadata, where `broken_link` is
+    /// whether it is a symbolic link whose target does not exist, or `None` if it is not colored.
+    pub fn color(&self, name: &str, metadata: &EntryMetadata, broken_link: bool) -> Option<&str> {
+        let mode = metadata.mode;
+        let (sticky, other_writable) = (mode & 0o1000 != 0, mode & 0o002 != 0);
+        // The keys which apply to the entry, most specific first, before checking endings.
+        let keys: Vec<&str> = match metadata.file_type() {
+            FileType::Dir => [
+                (sticky && other_writable, "tw"),
+                (other_writable, "ow"),
+                (sticky, "st"),
+            ]
+            .into_iter()
+            .filter_map(|(applies, key)| applies.then_some(key))
+            .chain(["di"])
+            .collect(),
+            FileType::File => [
+                (mode & 0o4000 != 0, "su"),
+                (mode & 0o2000 != 0, "sg"),
+                (metadata.is_executable(), "ex"),
+                (metadata.links > 1, "mh"),
+            ]
+            .into_iter()
+            .filter_map(|(applies, key)| applies.then_some(key))
+            .collect(),
+            FileType::Symlink if broken_link => vec!["or", "ln"],
+            FileType::Symlink => vec!["ln"],
+            FileType::Fifo => vec!["pi"],
+            FileType::Socket => vec!["so"],
+            FileType::BlockDevice => vec!["bd"],
+            FileType::CharDevice => vec!["cd"],
+            FileType::Unknown => Vec::new(),
+        };
+        if let Some(color) = keys.into_iter().find_map(|key| self.type_color(key)) {
+            return Some(color);
+        }
+        if metadata.file_type() != FileType::File {
+            return None;
+        }
+        self.ending_color(name).or_else(|| self.type_color("fi"))
+    }
+
+    /// Returns the color for the given file type key, unless it is not set or has no color.
+    fn type_color(&self, key: &str) -> Option<&str> {
+        let (_, color) = self.types.iter().rev().find(|(k, _)| k == key)?;
+        is_colored(color).then_some(color.as_str())
+    }
+
+    /// Returns the color for the last ending which the given name ends with.
+    fn ending_color(&self, name: &str) -> Option<&str> {
+        let lowercase = name.to_lowercase();
+        let (_, color) = (self.endings.iter().rev())
+            .find(|(ending, _)| name.ends_with(ending.as_str()))
+            .or_else(|| {
+                (self.endings.iter().rev())
+                    .find(|(ending, _)| lowercase.ends_with(&ending.to_lowercase()))
+            })?;
+        is_colored(color).then_some(color.as_str())
+    }
+}
+
+/// Returns whether the given color changes anything, as `0`, `00` or nothing resets to normal.
+fn is_colored(color: &str) -> bool {
+    !color.is_empty() && color.chars().any(|c| c != '0')
+}
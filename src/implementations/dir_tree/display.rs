@@ -0,0 +1,102 @@
+//! Options controlling what a [DirTree](super::DirTree) shows alongside the name of each entry.
+
+use super::colors::LsColors;
+
+/// [SizeFormat] defines how sizes are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeFormat {
+    /// The exact number of bytes, like `tree -s`.
+    Bytes,
+    /// Powers of 1000 with a unit suffix, like `tree --si`, such as `4.2k` or `13M`.
+    Si,
+    /// Powers of 1024 with a unit suffix, like `tree -h`, such as `4.2K` or `13M`.
+    Iec,
+}
+
+impl SizeFormat {
+    /// Formats the given number of bytes, right-aligned to the same width as `tree`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::dir_tree::SizeFormat;
+    ///
+    /// assert_eq!(SizeFormat::Bytes.format(4096), "       4096");
+    /// assert_eq!(SizeFormat::Iec.format(4096), "4.0K");
+    /// assert_eq!(SizeFormat::Si.format(4096), "4.1k");
+    /// assert_eq!(SizeFormat::Iec.format(1_363_149), "1.3M");
+    /// assert_eq!(SizeFormat::Iec.format(52_428_800), " 50M");
+    /// assert_eq!(SizeFormat::Iec.format(999), " 999");
+    /// ```
+    pub fn format(self, bytes: u64) -> String {
+        let (base, units) = match self {
+            SizeFormat::Bytes => return format!("{:>11}", bytes),
+            SizeFormat::Si => (1000.0, ["k", "M", "G", "T", "P", "E"]),
+            SizeFormat::Iec => (1024.0, ["K", "M", "G", "T", "P", "E"]),
+        };
+        if (bytes as f64) < base {
+            return format!("{:>4}", bytes);
+        }
+        let mut value = bytes as f64;
+        let mut unit = "";
+        for u in units {
+            value /= base;
+            unit = u;
+            // Stop once the value rounds to less than the base, so 1023.9K is written as 1.0M.
+            if value.round() < base {
+                break;
+            }
+        }
+        if value < 9.95 {
+            format!("{:.1}{}", value, unit)
+        } else {
+            format!("{:>3.0}{}", value, unit)
+        }
+    }
+}
+
+/// [DisplayOptions] collects the additional data shown with each entry of a
+/// [DirTree](super::DirTree).
+///
+/// With the default options, only the name of each entry is shown. Otherwise, the selected
+/// columns are shown in brackets before the name, in the order of the fields below, like
+/// `[  1837262 drwxr-xr-x root     root            4096 Nov 14 22:13]  name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayOptions {
+    /// Show the inode number of each entry, like `tree --inodes`.
+    pub inode: bool,
+    /// Show the mode of each entry, such as `drwxr-xr-x`, like `tree -p`.
+    pub permissions: bool,
+    /// Show the number of hard links to each entry.
+    pub links: bool,
+    /// Show the name of the user which owns each entry, or its id if it has no name, like
+    /// `tree -u`.
+    pub user: bool,
+    /// Show the name of the group which owns each entry, or its id if it has no name, like
+    /// `tree -g`.
+    pub group: bool,
+    /// If set, show the size of each entry, like `tree -s`.
+    pub size: Option<SizeFormat>,
+    /// Show the cumulative size of the contents of each directory rather than the size of the
+    /// directory entry itself, like `tree --du`. Has no effect unless `size` is set.
+    pub du: bool,
+    /// Show the compressed size of each entry of an archive after its size, in the same format,
+    /// or nothing for entries with no compressed size. Has no effect unless `size` is set.
+    pub compressed: bool,
+    /// If set, show the time each entry was last modified, like `tree -D`, written in UTC using
+    /// the given format (see [format_time](super::metadata::format_time)). `tree` uses
+    /// [DEFAULT_TIME_FORMAT].
+    pub modified: Option<String>,
+    /// Show the first digits of the hash of the contents of each regular file. Hashes are only
+    /// read if the tree is built with [DirTreeBuilder::hash](super::DirTreeBuilder::hash).
+    pub hash: bool,
+    /// Show an indicator after the name of each entry of certain types, like `tree -F`: `/` for
+    /// directories, `*` for executables, `@` for symbolic links, `|` for named pipes and `=` for
+    /// sockets.
+    pub classify: bool,
+    /// If set, color the name of each entry with the given colors, like `tree -C`.
+    pub colors: Option<LsColors>,
+}
+
+/// The format used by `tree -D` when no other is given.
+pub const DEFAULT_TIME_FORMAT: &str = "%b %e %H:%M";
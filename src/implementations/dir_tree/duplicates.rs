@@ -0,0 +1,191 @@
+//! Finding the files in a [DirTree] which have the same contents.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::hash::{Digest, hash_file};
+use super::{DirTree, ScanError, relative_str};
+use crate::node::Node;
+
+/// [DuplicateGroup] is a set of files with the same contents, as returned by
+/// [DirTree::duplicates].
+#[derive(Debug)]
+pub struct DuplicateGroup<'a> {
+    /// The SHA-256 hash of the contents of the files.
+    pub digest: Digest,
+    /// The size of each of the files, in bytes.
+    pub size: u64,
+    /// The nodes of the files, in the order they appear in the tree.
+    pub files: Vec<&'a DirTree>,
+}
+
+/// [Duplicates] holds every set of files in a [DirTree] with the same contents, as returned by
+/// [DirTree::duplicates].
+///
+/// It is displayed as a tree with a child for each group, showing the start of its hash, and a
+/// child of the group for the path of each of its files relative to the root.
+#[derive(Debug)]
+pub struct Duplicates<'a> {
+    root: &'a DirTree,
+    groups: Vec<DuplicateGroup<'a>>,
+}
+
+impl<'a> Duplicates<'a> {
+    /// Returns the groups of duplicate files, largest files first.
+    pub fn groups(&self) -> &[DuplicateGroup<'a>] {
+        &self.groups
+    }
+
+    /// Returns whether no two files have the same contents.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+impl DirTree {
+    /// Returns every set of regular files in the tree with the same contents, along with any
+    /// errors encountered reading files.
+    ///
+    /// Files are first grouped by size, and only files with the same size as another are hashed,
+    /// unless the tree was built with [DirTreeBuilder::hash](super::DirTreeBuilder::hash), in
+    /// which case those hashes are used. Empty files are not considered duplicates of each other,
+    /// and files which cannot be read are left out, as are later hard links to a file if the tree
+    /// was built with [DirTreeBuilder::hard_links](super::DirTreeBuilder::hard_links). Files which
+    /// were listed rather than read from the filesystem, such as by
+    /// [DirTreeBuilder::from_paths](super::DirTreeBuilder::from_paths), are not on disk to be
+    /// compared, so none are found in such a tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::paths::PathEntry;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-duplicates");
+    /// fs::create_dir_all(tmpdir.join("a")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("b")).unwrap();
+    /// fs::write(tmpdir.join("a/one"), "hello world").unwrap();
+    /// fs::write(tmpdir.join("b/copy"), "hello world").unwrap();
+    /// fs::write(tmpdir.join("b/other"), "hello there").unwrap();
+    /// fs::write(tmpdir.join("empty1"), "").unwrap();
+    /// fs::write(tmpdir.join("empty2"), "").unwrap();
+    ///
+    /// let root = DirTree::new(&tmpdir).unwrap();
+    /// let (duplicates, errors) = root.duplicates();
+    ///
+    /// // Listed files are not read, even if there are files at the same paths.
+    /// let listed = DirTree::builder(&tmpdir).from_paths(["a/one", "b/copy"].map(|path| PathEntry {
+    ///     path: path.to_string(),
+    ///     size: 11,
+    ///     ..PathEntry::default()
+    /// }));
+    /// assert!(listed.duplicates().0.is_empty());
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// assert!(errors.is_empty());
+    /// assert_eq!(duplicates.groups().len(), 1);
+    /// assert_eq!(format!("{}", duplicates), format!(
+    /// "{}
+    /// └── b94d27b9934d  (2 files, 11 bytes each)
+    ///     ├── a/one
+    ///     └── b/copy", tmpdir.display()));
+    /// ```
+    pub fn duplicates(&self) -> (Duplicates<'_>, Vec<ScanError>) {
+        let mut by_size: BTreeMap<u64, Vec<&DirTree>> = BTreeMap::new();
+        collect_files(self, &mut by_size);
+        let mut errors = Vec::new();
+        let mut groups = Vec::new();
+        for (size, files) in by_size {
+            if files.len() < 2 {
+                continue;
+            }
+            let mut by_digest: BTreeMap<Digest, Vec<&DirTree>> = BTreeMap::new();
+            for file in files {
+                let digest = match file.hash {
+                    Some(digest) => digest,
+                    None => match hash_file(&file.path) {
+                        Ok(digest) => digest,
+                        Err(error) => {
+                            errors.push(ScanError {
+                                path: file.path.clone(),
+                                error,
+                            });
+                            continue;
+                        }
+                    },
+                };
+                by_digest.entry(digest).or_default().push(file);
+            }
+            groups.extend(
+                by_digest
+                    .into_iter()
+                    .filter(|(_, files)| files.len() > 1)
+                    .map(|(digest, files)| DuplicateGroup {
+                        digest,
+                        size,
+                        files,
+                    }),
+            );
+        }
+        groups.sort_by_key(|group| Reverse(group.size));
+        let duplicates = Duplicates { root: self, groups };
+        (duplicates, errors)
+    }
+}
+
+/// Adds every non-empty regular file at or below the given node which is on disk to `by_size`, in
+/// tree order.
+pub(super) fn collect_files<'a>(node: &'a DirTree, by_size: &mut BTreeMap<u64, Vec<&'a DirTree>>) {
+    if node.metadata.is_file() && node.size > 0 && node.hard_link.is_none() && !node.listed {
+        by_size.entry(node.size).or_default().push(node);
+    }
+    for child in &node.children {
+        collect_files(child, by_size);
+    }
+}
+
+/// A node of the tree displaying [Duplicates], which only needs a label.
+struct Listing {
+    label: String,
+    children: Vec<Listing>,
+}
+
+impl Node for Listing {
+    fn value(&self) -> impl fmt::Display {
+        &self.label
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter()
+    }
+}
+
+impl fmt::Display for Duplicates<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = &self.root.path;
+        let groups = self.groups.iter().map(|group| Listing {
+            label: format!(
+                "{}  ({} files, {} bytes each)",
+                group.digest.short(),
+                group.files.len(),
+                group.size
+            ),
+            children: group
+                .files
+                .iter()
+                .map(|file| Listing {
+                    label: relative_str(file.path.strip_prefix(root).unwrap_or(&file.path)),
+                    children: Vec::new(),
+                })
+                .collect(),
+        });
+        let listing = Listing {
+            label: root.to_string_lossy().into_owned(),
+            children: groups.collect(),
+        };
+        Node::fmt(&listing, f)
+    }
+}
@@ -0,0 +1,201 @@
+//! Shell-style glob patterns, as used to filter the entries of a [DirTree](super::DirTree).
+
+/// A single element of a compiled [Glob].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Matches exactly this character.
+    Literal(char),
+    /// `?` matches any single character other than `/`.
+    Any,
+    /// `*` matches any sequence of characters other than `/`.
+    Star,
+    /// `**` not followed by `/` matches any sequence of characters, including `/`.
+    DoubleStar,
+    /// `**/` matches nothing, or any sequence of characters ending in `/`, so that it spans zero or
+    /// more whole directories.
+    AnyDirs,
+    /// `[...]` matches any single character other than `/` in (or, if negated, not in) the given
+    /// inclusive ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// [Glob] is a compiled shell-style pattern.
+///
+/// - `*` matches any run of characters except `/`.
+/// - `?` matches any single character except `/`.
+/// - `[abc]`, `[a-z]` and `[!a-z]` (or `[^a-z]`) match one character in, or not in, a set.
+/// - `**` matches across directories: `**/` matches zero or more whole directories, and a trailing
+///   `**` matches everything.
+/// - `\` makes the following character literal.
+///
+/// A `[` without a closing `]` matches a literal `[`.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::implementations::dir_tree::glob::Glob;
+///
+/// assert!(Glob::new("*.rs").matches("main.rs"));
+/// assert!(!Glob::new("*.rs").matches("src/main.rs"));
+/// assert!(Glob::new("src/*.rs").matches("src/main.rs"));
+/// assert!(Glob::new("**/*.rs").matches("main.rs"));
+/// assert!(Glob::new("**/*.rs").matches("src/bin/main.rs"));
+/// assert!(Glob::new("src/**").matches("src/bin/main.rs"));
+/// assert!(Glob::new("file?.[ch]").matches("file1.c"));
+/// assert!(!Glob::new("file?.[!ch]").matches("file1.c"));
+/// assert!(Glob::new("[a-c][0-9]").matches("b7"));
+/// assert!(Glob::new(r"\*").matches("*"));
+/// assert!(!Glob::new(r"\*").matches("a"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    /// Compiles the given pattern.
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let at_segment_start = i == 0 || chars[i - 1] == '/';
+                    let mut end = i;
+                    while chars.get(end) == Some(&'*') {
+                        end += 1;
+                    }
+                    if at_segment_start && chars.get(end) == Some(&'/') {
+                        tokens.push(Token::AnyDirs);
+                        end += 1;
+                    } else if at_segment_start && end == chars.len() {
+                        tokens.push(Token::DoubleStar);
+                    } else {
+                        // Consecutive asterisks anywhere else mean the same as a single one.
+                        tokens.push(Token::Star);
+                    }
+                    i = end;
+                    continue;
+                }
+                '*' => tokens.push(Token::Star),
+                '?' => tokens.push(Token::Any),
+                '[' => {
+                    if let Some((class, end)) = parse_class(&chars, i) {
+                        tokens.push(class);
+                        i = end;
+                        continue;
+                    }
+                    tokens.push(Token::Literal('['));
+                }
+                '\\' if i + 1 < chars.len() => {
+                    i += 1;
+                    tokens.push(Token::Literal(chars[i]));
+                }
+                c => tokens.push(Token::Literal(c)),
+            }
+            i += 1;
+        }
+        Glob {
+            pattern: pattern.to_string(),
+            tokens,
+        }
+    }
+
+    /// Returns the pattern this glob was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns whether the pattern contains a `/`, in which case it should be matched against a
+    /// path rather than a single file name.
+    pub fn has_separator(&self) -> bool {
+        self.tokens
+            .iter()
+            .any(|t| matches!(t, Token::Literal('/') | Token::AnyDirs | Token::DoubleStar))
+    }
+
+    /// Returns whether the whole of the given text matches the pattern.
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let n = text.len();
+        // `next[j]` holds whether the tokens after the current one match `text[j..]`.
+        let mut next = vec![false; n + 1];
+        next[n] = true;
+        for token in self.tokens.iter().rev() {
+            let mut current = vec![false; n + 1];
+            match token {
+                Token::Star | Token::DoubleStar => {
+                    current[n] = next[n];
+                    for j in (0..n).rev() {
+                        let can_extend = *token == Token::DoubleStar || text[j] != '/';
+                        current[j] = next[j] || (can_extend && current[j + 1]);
+                    }
+                }
+                Token::AnyDirs => {
+                    // Whether the tokens after this one match after some `/` at or after `j`.
+                    let mut after_slash = false;
+                    current[n] = next[n];
+                    for j in (0..n).rev() {
+                        after_slash = (text[j] == '/' && next[j + 1]) || after_slash;
+                        current[j] = next[j] || after_slash;
+                    }
+                }
+                _ => {
+                    for j in 0..n {
+                        current[j] = next[j + 1] && token_matches(token, text[j]);
+                    }
+                }
+            }
+            next = current;
+        }
+        next[0]
+    }
+}
+
+/// Returns whether the given single-character token matches the given character.
+fn token_matches(token: &Token, c: char) -> bool {
+    match token {
+        Token::Literal(l) => *l == c,
+        Token::Any => c != '/',
+        Token::Class { negated, ranges } => {
+            c != '/' && ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+        }
+        Token::Star | Token::DoubleStar | Token::AnyDirs => false,
+    }
+}
+
+/// Parses the character class starting with the `[` at `start`, returning it along with the index
+/// just past its closing `]`, or `None` if it is never closed.
+fn parse_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let mut c = *chars.get(i)?;
+        // A `]` straight after the opening bracket is part of the set rather than closing it.
+        if c == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        if c == '\\' {
+            i += 1;
+            c = *chars.get(i)?;
+        }
+        first = false;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&e| e != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Decompressing gzip streams with a built-in implementation of inflate, so that compressed
+//! archives can be read without any other libraries.
+//!
+//! The formats are described in RFC 1951 (deflate) and RFC 1952 (gzip).
+
+use std::io::{self, Read};
+use std::mem;
+
+/// How far back a deflate stream may refer to earlier output.
+const WINDOW: usize = 32 * 1024;
+
+/// The first length of each length code from 257, and the number of extra bits after it.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// The first distance of each distance code, and the number of extra bits after it.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order in which the lengths of the code length code are given in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The table for computing the CRC-32 of the decompressed data, which gzip records.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn unexpected_end() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "unexpected end of gzip stream",
+    )
+}
+
+/// Reads a stream one bit at a time, starting from the least significant bit of each byte.
+struct Bits<R> {
+    inner: R,
+    buffer: Vec<u8>,
+    pos: usize,
+    len: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<R: Read> Bits<R> {
+    /// Returns the next whole byte, skipping any bits left in the current one, or `None` at the
+    /// end of the stream.
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        self.bits = 0;
+        self.count = 0;
+        if self.pos == self.len {
+            self.len = loop {
+                match self.inner.read(&mut self.buffer) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    result => break result?,
+                }
+            };
+            self.pos = 0;
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+        self.pos += 1;
+        Ok(Some(self.buffer[self.pos - 1]))
+    }
+
+    /// Returns the next `n` bits, where `n` is at most 16.
+    fn take(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let (bits, count) = (self.bits, self.count);
+            let byte = self.byte()?.ok_or_else(unexpected_end)?;
+            self.bits = bits | (byte as u32) << count;
+            self.count = count + 8;
+        }
+        let value = self.bits & ((1 << n) - 1);
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Returns the next `n` whole bytes as a little-endian number, where `n` is at most 4.
+    fn bytes(&mut self, n: usize) -> io::Result<u32> {
+        let mut value = 0;
+        for i in 0..n {
+            value |= (self.byte()?.ok_or_else(unexpected_end)? as u32) << (8 * i);
+        }
+        Ok(value)
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    // The number of codes of each length.
+    counts: [u16; 16],
+    // The symbols, ordered by their codes.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Returns the code in which symbol `i` has a code of length `lengths[i]`, or `0` if unused.
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = 2 * left - count as i32;
+            if left < 0 {
+                return Err(invalid("invalid deflate code lengths"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// Returns the next symbol in the given stream.
+    fn decode<R: Read>(&self, bits: &mut Bits<R>) -> io::Result<u16> {
+        // The code read so far, the first code of the current length, and its index in symbols.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= bits.take(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid deflate code"))
+    }
+}
+
+/// The deflate block being decompressed.
+enum Block {
+    /// Between blocks.
+    None,
+    /// A stored block with the given number of bytes left.
+    Stored(usize),
+    /// A compressed block with the given literal and length code, and distance code.
+    Huffman(Huffman, Huffman),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the header of a gzip member, or the end of the stream.
+    Header,
+    Body,
+    Done,
+}
+
+/// [GzDecoder] decompresses a gzip stream read from another reader, as produced by `gzip` or
+/// `tar -z`. Streams of several gzip members are decompressed as their concatenation, and
+/// anything after the last member which is not another member is ignored, as by `gzip`.
+///
+/// # Errors
+///
+/// Reading fails with [io::ErrorKind::InvalidData] if the stream is not gzip, is corrupt, or its
+/// checksum does not match, and with [io::ErrorKind::UnexpectedEof] if it is cut short.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use simple_tree::implementations::dir_tree::gzip::GzDecoder;
+///
+/// // `printf 'hello hello hello\n' | gzip -n`
+/// let compressed = [
+///     0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9, 0xc9,
+///     0x57, 0xc8, 0x40, 0x90, 0x5c, 0x00, 0x3b, 0x7c, 0x8a, 0xdf, 0x12, 0x00, 0x00, 0x00,
+/// ];
+/// let mut text = String::new();
+/// GzDecoder::new(&compressed[..]).read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "hello hello hello\n");
+///
+/// let mut corrupt = compressed;
+/// corrupt[22] ^= 1;
+/// assert!(GzDecoder::new(&corrupt[..]).read_to_string(&mut text).is_err());
+/// ```
+pub struct GzDecoder<R> {
+    bits: Bits<R>,
+    state: State,
+    block: Block,
+    // Whether the current block is the last of the member.
+    last: bool,
+    // Whether a whole member has been read.
+    members: bool,
+    // The output, including the window before the part which has not been read yet.
+    history: Vec<u8>,
+    // The index in history of the first byte which has not been read, and of the first byte not
+    // yet included in the checksum.
+    read: usize,
+    checked: usize,
+    crc: u32,
+    size: u32,
+}
+
+impl<R: Read> GzDecoder<R> {
+    /// Returns a decoder of the gzip stream read from `inner`, which is read as it is decoded.
+    pub fn new(inner: R) -> Self {
+        GzDecoder {
+            bits: Bits {
+                inner,
+                buffer: vec![0; 16 * 1024],
+                pos: 0,
+                len: 0,
+                bits: 0,
+                count: 0,
+            },
+            state: State::Header,
+            block: Block::None,
+            last: false,
+            members: false,
+            history: Vec::new(),
+            read: 0,
+            checked: 0,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// Reads the header of the next member, returning whether there is one.
+    fn header(&mut self) -> io::Result<bool> {
+        let magic = match self.bits.byte()? {
+            Some(0x1f) => self.bits.byte()?,
+            Some(_) | None if self.members => return Ok(false),
+            Some(_) => None,
+            None => return Err(unexpected_end()),
+        };
+        if magic != Some(0x8b) {
+            return match self.members {
+                true => Ok(false),
+                false => Err(invalid("not a gzip stream")),
+            };
+        }
+        if self.bits.bytes(1)? != 8 {
+            return Err(invalid("unsupported gzip compression method"));
+        }
+        let flags = self.bits.bytes(1)?;
+        // The modification time, extra flags and operating system.
+        self.bits.bytes(4)?;
+        self.bits.bytes(2)?;
+        if flags & 0x04 != 0 {
+            let length = self.bits.bytes(2)?;
+            for _ in 0..length {
+                self.bits.bytes(1)?;
+            }
+        }
+        // The file name and comment are each terminated by a zero byte.
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                while self.bits.bytes(1)? != 0 {}
+            }
+        }
+        if flags & 0x02 != 0 {
+            self.bits.bytes(2)?;
+        }
+        self.last = false;
+        self.crc = 0;
+        self.size = 0;
+        Ok(true)
+    }
+
+    /// Adds the output since the last call to the checksum and size of the member.
+    fn update_check(&mut self) {
+        let mut crc = !self.crc;
+        for &byte in &self.history[self.checked..] {
+            crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.crc = !crc;
+        self.size = (self.size as usize).wrapping_add(self.history.len() - self.checked) as u32;
+        self.checked = self.history.len();
+    }
+
+    /// Decompresses up to about a window of output, or up to the end of the current member.
+    fn inflate(&mut self) -> io::Result<()> {
+        // Drop what has been read, but keep the window which later output may refer to.
+        let drop = self.read.min(self.history.len().saturating_sub(WINDOW));
+        if drop >= WINDOW {
+            self.update_check();
+            self.history.drain(..drop);
+            self.read -= drop;
+            self.checked -= drop;
+        }
+        let limit = self.history.len() + WINDOW;
+        while self.history.len() < limit {
+            match mem::replace(&mut self.block, Block::None) {
+                Block::None if self.last => {
+                    self.update_check();
+                    let (crc, size) = (self.bits.bytes(4)?, self.bits.bytes(4)?);
+                    if crc != self.crc || size != self.size {
+                        return Err(invalid("gzip checksum does not match"));
+                    }
+                    self.members = true;
+                    self.state = State::Header;
+                    return Ok(());
+                }
+                Block::None => self.block = self.block_header()?,
+                Block::Stored(left) => {
+                    let n = left.min(limit - self.history.len());
+                    for _ in 0..n {
+                        let byte = self.bits.byte()?.ok_or_else(unexpected_end)?;
+                        self.history.push(byte);
+                    }
+                    if left > n {
+                        self.block = Block::Stored(left - n);
+                    }
+                }
+                Block::Huffman(literals, distances) => {
+                    if self.codes(&literals, &distances, limit)? {
+                        self.block = Block::Huffman(literals, distances);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the header of the next block.
+    fn block_header(&mut self) -> io::Result<Block> {
+        self.last = self.bits.take(1)? == 1;
+        match self.bits.take(2)? {
+            0 => {
+                let (length, complement) = (self.bits.bytes(2)?, self.bits.bytes(2)?);
+                if length != !complement & 0xffff {
+                    return Err(invalid("invalid stored block length"));
+                }
+                Ok(Block::Stored(length as usize))
+            }
+            1 => {
+                let mut lengths = [8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                Ok(Block::Huffman(
+                    Huffman::new(&lengths)?,
+                    Huffman::new(&[5; 30])?,
+                ))
+            }
+            2 => {
+                let literals = self.bits.take(5)? as usize + 257;
+                let distances = self.bits.take(5)? as usize + 1;
+                let code_lengths = self.bits.take(4)? as usize + 4;
+                let mut lengths = [0; 19];
+                for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+                    lengths[i] = self.bits.take(3)? as u8;
+                }
+                let code = Huffman::new(&lengths)?;
+                let mut lengths = vec![0; literals + distances];
+                let mut i = 0;
+                while i < lengths.len() {
+                    let (value, repeat) = match code.decode(&mut self.bits)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 if i > 0 => (lengths[i - 1], 3 + self.bits.take(2)? as usize),
+                        17 => (0, 3 + self.bits.take(3)? as usize),
+                        18 => (0, 11 + self.bits.take(7)? as usize),
+                        _ => return Err(invalid("invalid deflate code lengths")),
+                    };
+                    if i + repeat > lengths.len() {
+                        return Err(invalid("invalid deflate code lengths"));
+                    }
+                    lengths[i..i + repeat].fill(value);
+                    i += repeat;
+                }
+                Ok(Block::Huffman(
+                    Huffman::new(&lengths[..literals])?,
+                    Huffman::new(&lengths[literals..])?,
+                ))
+            }
+            _ => Err(invalid("invalid deflate block type")),
+        }
+    }
+
+    /// Decodes the codes of a compressed block until the output reaches `limit`, returning
+    /// whether the block continues.
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman, limit: usize) -> io::Result<bool> {
+        while self.history.len() < limit {
+            let symbol = literals.decode(&mut self.bits)? as usize;
+            if symbol < 256 {
+                self.history.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                return Ok(false);
+            }
+            let i = symbol - 257;
+            if i >= LENGTH_BASE.len() {
+                return Err(invalid("invalid deflate length"));
+            }
+            let length = LENGTH_BASE[i] as usize + self.bits.take(LENGTH_EXTRA[i] as u32)? as usize;
+            let i = distances.decode(&mut self.bits)? as usize;
+            if i >= DISTANCE_BASE.len() {
+                return Err(invalid("invalid deflate distance"));
+            }
+            let distance =
+                DISTANCE_BASE[i] as usize + self.bits.take(DISTANCE_EXTRA[i] as u32)? as usize;
+            if distance > self.history.len() {
+                return Err(invalid("invalid deflate distance"));
+            }
+            // The copy may overlap the output it produces, so copy one byte at a time.
+            let start = self.history.len() - distance;
+            for j in 0..length {
+                self.history.push(self.history[start + j]);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.read < self.history.len() {
+                let n = buf.len().min(self.history.len() - self.read);
+                buf[..n].copy_from_slice(&self.history[self.read..self.read + n]);
+                self.read += n;
+                return Ok(n);
+            }
+            match self.state {
+                State::Done => return Ok(0),
+                State::Header => {
+                    self.state = match self.header()? {
+                        true => State::Body,
+                        false => State::Done,
+                    }
+                }
+                State::Body => self.inflate()?,
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Support for `.gitignore`, `.ignore` and `.git/info/exclude` files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::glob::Glob;
+
+/// The ignore files read from each directory, from lowest to highest precedence.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// The ignore file of a git repository which is not part of its contents, read from its top.
+const EXCLUDE_FILE: &str = ".git/info/exclude";
+
+/// A single non-empty, non-comment line of an ignore file.
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: Glob,
+    /// The line began with `!`, so matching paths are re-included.
+    negated: bool,
+    /// The line ended with `/`, so only directories match.
+    dir_only: bool,
+    /// The line contained a `/` before its end, so it is matched against the whole path relative
+    /// to the directory of the ignore file rather than just the name of the entry.
+    anchored: bool,
+}
+
+/// [IgnoreFile] holds the rules of one ignore file, which use the same syntax as `.gitignore`.
+///
+/// - Blank lines and lines beginning with `#` are skipped. Trailing spaces are dropped unless
+///   escaped with `\`.
+/// - A leading `!` re-includes paths excluded by earlier rules. Use `\!` and `\#` for a literal
+///   `!` or `#`.
+/// - A trailing `/` makes the rule match only directories.
+/// - A rule containing any other `/` is anchored to the directory of the ignore file, and is
+///   matched against the whole relative path. Other rules match the name of an entry at any depth.
+/// - Patterns are [Glob]s, so `**` matches any number of directories.
+///
+/// Later rules take precedence over earlier ones.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::implementations::dir_tree::ignore::IgnoreFile;
+///
+/// let rules = IgnoreFile::parse("
+/// # Build output
+/// target/
+/// *.log
+/// !keep.log
+/// /TODO
+/// docs/**/*.tmp
+/// ");
+///
+/// assert_eq!(rules.matches("target", true), Some(true));
+/// assert_eq!(rules.matches("target", false), None);
+/// assert_eq!(rules.matches("crates/foo/target", true), Some(true));
+/// assert_eq!(rules.matches("logs/build.log", false), Some(true));
+/// assert_eq!(rules.matches("logs/keep.log", false), Some(false));
+/// assert_eq!(rules.matches("TODO", false), Some(true));
+/// assert_eq!(rules.matches("src/TODO", false), None);
+/// assert_eq!(rules.matches("docs/a/b/c.tmp", false), Some(true));
+/// assert_eq!(rules.matches("docs/c.tmp", false), Some(true));
+/// assert_eq!(rules.matches("c.tmp", false), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    /// Parses the contents of an ignore file.
+    pub fn parse(contents: &str) -> Self {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        IgnoreFile { rules }
+    }
+
+    /// Returns `Some(true)` if the entry with the given path, relative to the directory of the
+    /// ignore file and separated by `/`, is ignored by these rules, `Some(false)` if it is
+    /// explicitly re-included, or `None` if no rule matches it.
+    pub fn matches(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .find(|rule| {
+                if rule.anchored {
+                    rule.glob.matches(path)
+                } else {
+                    rule.glob.matches(name)
+                }
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Parses a single line of an ignore file, returning `None` if it holds no rule.
+fn parse_rule(line: &str) -> Option<Rule> {
+    if line.starts_with('#') {
+        return None;
+    }
+    // Drop trailing spaces, unless the last one is escaped.
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    if line.is_empty() {
+        return None;
+    }
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    Some(Rule {
+        glob: Glob::new(line),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+/// The ignore files which apply to a directory, from the top of the repository down. The files
+/// are shared, so the stack is cheap to clone for each directory.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IgnoreStack {
+    /// Each ignore file along with the path of its directory relative to the top, ending in `/`
+    /// unless it is the top itself.
+    files: Vec<Arc<(String, IgnoreFile)>>,
+    /// The path of the root of the tree relative to the top, ending in `/` unless they are the
+    /// same.
+    root_prefix: String,
+}
+
+impl IgnoreStack {
+    /// Returns the ignore files which apply to the given root of a tree before any of its own
+    /// are read. If the root is within a git repository, these are the repository's exclude file,
+    /// followed by the ignore files of each directory from the top of the repository down to the
+    /// parent of the root.
+    pub(crate) fn for_root(root: &Path) -> Self {
+        let mut stack = IgnoreStack::default();
+        let Ok(root) = root.canonicalize() else {
+            return stack;
+        };
+        let Some(top) = root.ancestors().find(|dir| dir.join(".git").exists()) else {
+            return stack;
+        };
+        if let Ok(contents) = fs::read_to_string(top.join(EXCLUDE_FILE)) {
+            let file = IgnoreFile::parse(&contents);
+            stack.files.push(Arc::new((String::new(), file)));
+        }
+        let relative = root.strip_prefix(top).unwrap_or(Path::new(""));
+        let mut dir = PathBuf::from(top);
+        let mut prefix = String::new();
+        for component in relative.components() {
+            stack.push_dir(&dir, &prefix);
+            dir.push(component);
+            prefix.push_str(&component.as_os_str().to_string_lossy());
+            prefix.push('/');
+        }
+        stack.root_prefix = prefix;
+        stack
+    }
+
+    /// Reads the ignore files in the given directory, whose path relative to the root of the tree
+    /// is given, so that they apply to its contents.
+    pub(crate) fn enter(&mut self, dir: &Path, relative: &str) {
+        let prefix = match relative {
+            "" => self.root_prefix.clone(),
+            _ => format!("{}{}/", self.root_prefix, relative),
+        };
+        self.push_dir(dir, &prefix);
+    }
+
+    fn push_dir(&mut self, dir: &Path, prefix: &str) {
+        for name in IGNORE_FILES {
+            if let Ok(contents) = fs::read_to_string(dir.join(name)) {
+                let file = IgnoreFile::parse(&contents);
+                self.files.push(Arc::new((prefix.to_string(), file)));
+            }
+        }
+    }
+
+    /// Returns whether the entry with the given path relative to the root of the tree is ignored.
+    pub(crate) fn is_ignored(&self, relative: &str, is_dir: bool) -> bool {
+        let path = format!("{}{}", self.root_prefix, relative);
+        self.files
+            .iter()
+            .rev()
+            .find_map(|entry| {
+                let (prefix, file) = entry.as_ref();
+                let path = path.strip_prefix(prefix.as_str())?;
+                file.matches(path, is_dir)
+            })
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,351 @@
+//! The metadata recorded for each entry of a [DirTree](super::DirTree), and how it is formatted.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file type bits of a unix mode.
+pub(super) const S_IFMT: u32 = 0o170000;
+pub(super) const S_IFSOCK: u32 = 0o140000;
+pub(super) const S_IFLNK: u32 = 0o120000;
+pub(super) const S_IFREG: u32 = 0o100000;
+pub(super) const S_IFBLK: u32 = 0o060000;
+pub(super) const S_IFDIR: u32 = 0o040000;
+pub(super) const S_IFCHR: u32 = 0o020000;
+pub(super) const S_IFIFO: u32 = 0o010000;
+
+/// [FileType] is the type of an entry, as recorded in the file type bits of its mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Dir,
+    File,
+    Symlink,
+    /// A named pipe.
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    /// The type is not one of the above, or the metadata could not be read.
+    Unknown,
+}
+
+/// [EntryMetadata] holds the parts of a file's metadata which a [DirTree](super::DirTree) can
+/// display.
+///
+/// On platforms other than unix, the mode is approximated from the file type and read-only flag,
+/// the owner, group, inode and device are all `0`, and the link count is `1`. The default is
+/// used for entries whose metadata could not be read, and is all zeros.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryMetadata {
+    /// The unix mode, including the file type bits.
+    pub mode: u32,
+    /// The id of the user which owns the file.
+    pub uid: u32,
+    /// The id of the group which owns the file.
+    pub gid: u32,
+    /// The inode number of the file.
+    pub inode: u64,
+    /// The id of the device containing the file.
+    pub device: u64,
+    /// The number of hard links to the file.
+    pub links: u64,
+    /// The time the file was last modified, if the platform records it.
+    pub modified: Option<SystemTime>,
+}
+
+impl EntryMetadata {
+    /// Returns the [EntryMetadata] recorded in the given filesystem metadata.
+    #[cfg(unix)]
+    pub fn from_fs(metadata: &fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        EntryMetadata {
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            inode: metadata.ino(),
+            device: metadata.dev(),
+            links: metadata.nlink(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Returns the [EntryMetadata] recorded in the given filesystem metadata.
+    #[cfg(not(unix))]
+    pub fn from_fs(metadata: &fs::Metadata) -> Self {
+        let file_type = metadata.file_type();
+        let kind = if file_type.is_dir() {
+            S_IFDIR
+        } else if file_type.is_symlink() {
+            S_IFLNK
+        } else {
+            S_IFREG
+        };
+        let permissions = if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        };
+        let execute = if file_type.is_dir() { 0o111 } else { 0 };
+        EntryMetadata {
+            mode: kind | permissions | execute,
+            uid: 0,
+            gid: 0,
+            inode: 0,
+            device: 0,
+            links: 1,
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Returns the mode as a string in the style of `ls -l`, such as `drwxr-xr-x`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::dir_tree::EntryMetadata;
+    ///
+    /// let mut metadata = EntryMetadata {
+    ///     mode: 0o040755,
+    ///     uid: 0,
+    ///     gid: 0,
+    ///     inode: 0,
+    ///     device: 0,
+    ///     links: 1,
+    ///     modified: None,
+    /// };
+    /// assert_eq!(metadata.mode_string(), "drwxr-xr-x");
+    ///
+    /// metadata.mode = 0o104755;
+    /// assert_eq!(metadata.mode_string(), "-rwsr-xr-x");
+    ///
+    /// metadata.mode = 0o041777;
+    /// assert_eq!(metadata.mode_string(), "drwxrwxrwt");
+    ///
+    /// metadata.mode = 0o120777;
+    /// assert_eq!(metadata.mode_string(), "lrwxrwxrwx");
+    /// ```
+    pub fn mode_string(&self) -> String {
+        let kind = match self.file_type() {
+            FileType::Dir => 'd',
+            FileType::Symlink => 'l',
+            FileType::Fifo => 'p',
+            FileType::Socket => 's',
+            FileType::BlockDevice => 'b',
+            FileType::CharDevice => 'c',
+            FileType::File => '-',
+            FileType::Unknown => '?',
+        };
+        let mut s = String::with_capacity(10);
+        s.push(kind);
+        // Each triple is the read, write and execute bits, along with the special bit which
+        // replaces the execute character, and the characters used for it with and without execute.
+        let triples = [
+            (0o400, 0o200, 0o100, 0o4000, 's', 'S'),
+            (0o040, 0o020, 0o010, 0o2000, 's', 'S'),
+            (0o004, 0o002, 0o001, 0o1000, 't', 'T'),
+        ];
+        for (r, w, x, special, with_x, without_x) in triples {
+            s.push(if self.mode & r != 0 { 'r' } else { '-' });
+            s.push(if self.mode & w != 0 { 'w' } else { '-' });
+            s.push(match (self.mode & x != 0, self.mode & special != 0) {
+                (true, true) => with_x,
+                (false, true) => without_x,
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        s
+    }
+
+    /// Returns the type of the entry.
+    pub fn file_type(&self) -> FileType {
+        match self.mode & S_IFMT {
+            S_IFDIR => FileType::Dir,
+            S_IFREG => FileType::File,
+            S_IFLNK => FileType::Symlink,
+            S_IFIFO => FileType::Fifo,
+            S_IFSOCK => FileType::Socket,
+            S_IFBLK => FileType::BlockDevice,
+            S_IFCHR => FileType::CharDevice,
+            _ => FileType::Unknown,
+        }
+    }
+
+    /// Returns whether the entry is a regular file, rather than a directory, symbolic link or
+    /// other kind of file.
+    pub fn is_file(&self) -> bool {
+        self.file_type() == FileType::File
+    }
+
+    /// Returns whether the entry is a regular file which anyone may execute.
+    pub fn is_executable(&self) -> bool {
+        self.is_file() && self.mode & 0o111 != 0
+    }
+}
+
+/// Returns the name of the user with the given id according to `/etc/passwd`, or the id itself if
+/// there is no such user.
+pub fn user_name(uid: u32) -> String {
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    USERS
+        .get_or_init(|| read_id_names("/etc/passwd"))
+        .get(&uid)
+        .cloned()
+        .unwrap_or_else(|| uid.to_string())
+}
+
+/// Returns the name of the group with the given id according to `/etc/group`, or the id itself if
+/// there is no such group.
+pub fn group_name(gid: u32) -> String {
+    static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    GROUPS
+        .get_or_init(|| read_id_names("/etc/group"))
+        .get(&gid)
+        .cloned()
+        .unwrap_or_else(|| gid.to_string())
+}
+
+/// Reads a file in the format of `/etc/passwd` or `/etc/group`, where each line has a name in the
+/// first field and an id in the third, and returns the name for each id. Where several names
+/// share an id, the first is used.
+fn read_id_names(path: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let Ok(contents) = fs::read_to_string(path) else {
+        return names;
+    };
+    for line in contents.lines() {
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(id) = id.parse() {
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Formats the given time in UTC according to a `strftime`-style format.
+///
+/// The supported conversions are `%Y`, `%y`, `%m`, `%d`, `%e`, `%H`, `%I`, `%M`, `%S`, `%p`,
+/// `%j`, `%b`, `%B`, `%a`, `%A`, `%s`, `%F` (`%Y-%m-%d`), `%T` (`%H:%M:%S`), `%R` (`%H:%M`) and
+/// `%%`. Anything else is copied as is. Times before 1970 are written as 1970.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use simple_tree::implementations::dir_tree::metadata::format_time;
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// assert_eq!(format_time(time, "%F %T"), "2023-11-14 22:13:20");
+/// assert_eq!(format_time(time, "%b %e %H:%M"), "Nov 14 22:13");
+/// assert_eq!(format_time(time, "%a %d %B %Y, %I:%M %p"), "Tue 14 November 2023, 10:13 PM");
+/// assert_eq!(format_time(UNIX_EPOCH, "%e/%m/%y %j"), " 1/01/70 001");
+/// ```
+pub fn format_time(time: SystemTime, format: &str) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let days = secs.div_euclid(86400);
+    let seconds_of_day = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let (hour, minute, second) = (
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+    );
+    let weekday = (days + 4).rem_euclid(7) as usize;
+    let day_of_year = days - days_from_civil(year, 1, 1) + 1;
+
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let Some(spec) = chars.next() else {
+            out.push('%');
+            break;
+        };
+        let piece = match spec {
+            'Y' => year.to_string(),
+            'y' => format!("{:02}", year % 100),
+            'm' => format!("{:02}", month),
+            'd' => format!("{:02}", day),
+            'e' => format!("{:>2}", day),
+            'H' => format!("{:02}", hour),
+            'I' => format!("{:02}", (hour + 11) % 12 + 1),
+            'M' => format!("{:02}", minute),
+            'S' => format!("{:02}", second),
+            'p' => String::from(if hour < 12 { "AM" } else { "PM" }),
+            'j' => format!("{:03}", day_of_year),
+            'b' => MONTHS[month as usize - 1][..3].to_string(),
+            'B' => MONTHS[month as usize - 1].to_string(),
+            'a' => WEEKDAYS[weekday][..3].to_string(),
+            'A' => WEEKDAYS[weekday].to_string(),
+            's' => secs.to_string(),
+            'F' => format!("{}-{:02}-{:02}", year, month, day),
+            'T' => format!("{:02}:{:02}:{:02}", hour, minute, second),
+            'R' => format!("{:02}:{:02}", hour, minute),
+            '%' => String::from("%"),
+            other => format!("%{}", other),
+        };
+        out.push_str(&piece);
+    }
+    out
+}
+
+/// Returns the year, month and day of the given number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Returns the number of days since 1970-01-01 of the given date.
+pub(super) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
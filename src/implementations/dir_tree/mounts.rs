@@ -0,0 +1,112 @@
+//! Finding the mount points of filesystems, from `/proc/self/mountinfo` on Linux.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The file listing the mounts visible to the current process, on Linux.
+const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// [Mount] is a filesystem mounted somewhere, as listed in `/proc/self/mountinfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    /// Where the filesystem is mounted.
+    pub mount_point: PathBuf,
+    /// The type of the filesystem, such as `ext4`, `proc` or `nfs4`.
+    pub fs_type: String,
+    /// Where the filesystem comes from, such as a device or a network share, or `none`.
+    pub source: String,
+}
+
+/// [MountTable] holds the filesystems mounted at each mount point, as read by
+/// [DirTreeBuilder::mounts](super::DirTreeBuilder::mounts) to annotate mount points.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use simple_tree::implementations::dir_tree::mounts::MountTable;
+///
+/// let table = MountTable::parse(
+///     "22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+/// 23 22 0:21 / /proc rw,nosuid shared:2 - proc proc rw
+/// 40 22 0:35 / /mnt/backup\\040drive rw - nfs4 server:/export rw,vers=4.2
+/// 41 22 0:36 / /proc rw - tmpfs none rw",
+/// );
+///
+/// assert_eq!(table.get(Path::new("/")).unwrap().fs_type, "ext4");
+/// assert_eq!(table.get(Path::new("/mnt/backup drive")).unwrap().source, "server:/export");
+/// // The last filesystem mounted at a mount point hides the others.
+/// assert_eq!(table.get(Path::new("/proc")).unwrap().fs_type, "tmpfs");
+/// assert_eq!(table.get(Path::new("/mnt")), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MountTable {
+    mounts: Vec<Mount>,
+}
+
+impl MountTable {
+    /// Reads the mounts visible to the current process from `/proc/self/mountinfo`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the file cannot be read, such as on platforms other than
+    /// Linux.
+    pub fn read() -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(MOUNTINFO)?))
+    }
+
+    /// Parses mounts in the format of `/proc/self/mountinfo`, ignoring any malformed lines.
+    ///
+    /// Each line has the id of the mount and of its parent, the device, the root of the mount
+    /// within its filesystem, the mount point and the mount options, followed by any number of
+    /// optional fields, a `-`, the filesystem type, the source and the filesystem options.
+    /// Spaces, tabs, newlines and backslashes in paths are escaped in octal, such as `\040`.
+    pub fn parse(s: &str) -> Self {
+        let mounts = s
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(' ').collect();
+                let separator = fields.iter().skip(6).position(|&f| f == "-")? + 6;
+                Some(Mount {
+                    mount_point: PathBuf::from(unescape(fields.get(4)?)),
+                    fs_type: unescape(fields.get(separator + 1)?),
+                    source: unescape(fields.get(separator + 2)?),
+                })
+            })
+            .collect();
+        MountTable { mounts }
+    }
+
+    /// Returns the filesystem mounted at the given absolute path, if it is a mount point. If
+    /// several are mounted there, returns the last, which hides the others.
+    pub fn get(&self, path: &Path) -> Option<&Mount> {
+        self.mounts.iter().rev().find(|m| m.mount_point == path)
+    }
+
+    /// Returns every mount, in the order they were mounted.
+    pub fn mounts(&self) -> &[Mount] {
+        &self.mounts
+    }
+}
+
+/// Replaces each octal escape such as `\040` in the given field with the character it stands for.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escape.and_then(|e| u8::from_str_radix(std::str::from_utf8(e).ok()?, 8).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,228 @@
+//! Building a [DirTree] from a list of paths, without reading the filesystem.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::metadata::{FileType, S_IFDIR, S_IFLNK, S_IFMT, S_IFREG};
+use super::{DirTree, DirTreeBuilder, EntryMetadata, LinkStatus, SpecialFile};
+
+/// [PathEntry] is a path given to [DirTreeBuilder::from_paths], along with anything known about
+/// the entry at that path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathEntry {
+    /// The path of the entry relative to the root, with components separated by the separator
+    /// set by [DirTreeBuilder::separator]. A path ending with the separator is a directory.
+    pub path: String,
+    /// The size of the entry, in bytes.
+    pub size: u64,
+    /// The metadata of the entry, if known. If it is not, or its mode has no file type, the
+    /// entry is a directory if anything is listed below it, a symbolic link if it has a target,
+    /// and a regular file otherwise.
+    pub metadata: Option<EntryMetadata>,
+    /// The target of the entry, if it is a symbolic link.
+    pub target: Option<String>,
+    /// The compressed size of the entry, in bytes, if it is an entry of a compressed archive.
+    pub compressed_size: Option<u64>,
+    /// The path of an earlier entry this is a hard link to, with components separated by the
+    /// separator, if it is an entry of an archive which is stored as a hard link.
+    pub hard_link: Option<String>,
+}
+
+impl From<&str> for PathEntry {
+    fn from(path: &str) -> Self {
+        PathEntry::from(path.to_string())
+    }
+}
+
+impl From<String> for PathEntry {
+    fn from(path: String) -> Self {
+        PathEntry {
+            path,
+            ..PathEntry::default()
+        }
+    }
+}
+
+/// An entry of a list of paths, before the tree is built from it.
+#[derive(Debug, Default)]
+struct Listed {
+    is_dir: bool,
+    size: u64,
+    metadata: Option<EntryMetadata>,
+    target: Option<String>,
+    compressed_size: Option<u64>,
+    hard_link: Option<String>,
+    // The children, in the order they were first listed, along with the index of each name.
+    children: Vec<(String, Listed)>,
+    index: HashMap<String, usize>,
+}
+
+impl Listed {
+    /// Returns the child with the given name, adding it if it has not been listed yet.
+    fn child(&mut self, name: &str) -> &mut Listed {
+        let i = *self.index.entry(name.to_string()).or_insert_with(|| {
+            self.children.push((name.to_string(), Listed::default()));
+            self.children.len() - 1
+        });
+        &mut self.children[i].1
+    }
+}
+
+impl DirTreeBuilder {
+    /// Builds a tree from the given list of paths, like `tree --fromfile`, without reading the
+    /// filesystem. The paths are relative to the root of the builder, which is only used as the
+    /// name of the root, and every directory above a listed path is added to the tree.
+    ///
+    /// The tree is built according to the builder's options, like [Self::build], except for
+    /// [Self::gitignore], [Self::follow_links] and [Self::threads], which only apply to reading
+    /// the filesystem. Empty components of paths are ignored, as are components which are `.`,
+    /// so `./a//b` is the same as `a/b`. If a path is listed more than once, the last entry for
+    /// it is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::{DisplayOptions, SizeFormat};
+    /// use simple_tree::implementations::dir_tree::paths::PathEntry;
+    ///
+    /// let files = "src/main.rs\nsrc/bin/\nREADME.md\n./src/lib.rs";
+    /// let root = DirTree::builder("repo").from_paths(files.lines());
+    /// assert_eq!(format!("{}", root),
+    /// "repo
+    /// ├── README.md
+    /// └── src
+    ///     ├── bin
+    ///     ├── lib.rs
+    ///     └── main.rs");
+    ///
+    /// let objects = [("logs|2024|jan.gz", 2048), ("logs|2024|feb.gz", 1024), ("index.html", 10)];
+    /// let root = DirTree::builder("s3://bucket")
+    ///     .separator("|")
+    ///     .dirs_first(true)
+    ///     .display(DisplayOptions {
+    ///         size: Some(SizeFormat::Iec),
+    ///         du: true,
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .from_paths(objects.map(|(path, size)| PathEntry {
+    ///         path: path.to_string(),
+    ///         size,
+    ///         ..PathEntry::default()
+    ///     }));
+    /// assert_eq!(format!("{}", root),
+    /// "[3.0K]  s3://bucket
+    /// ├── [3.0K]  logs
+    /// │   └── [3.0K]  2024
+    /// │       ├── [1.0K]  feb.gz
+    /// │       └── [2.0K]  jan.gz
+    /// └── [  10]  index.html");
+    /// ```
+    pub fn from_paths<I>(&self, paths: I) -> DirTree
+    where
+        I: IntoIterator,
+        I::Item: Into<PathEntry>,
+    {
+        let separator = self.separator.as_str();
+        let mut root = Listed::default();
+        for entry in paths {
+            let entry = entry.into();
+            let components: Vec<&str> = entry
+                .path
+                .split(separator)
+                .filter(|c| !c.is_empty() && *c != ".")
+                .collect();
+            let Some((last, parents)) = components.split_last() else {
+                continue;
+            };
+            let mut listed = &mut root;
+            for name in parents {
+                listed = listed.child(name);
+                listed.is_dir = true;
+            }
+            let listed = listed.child(last);
+            listed.is_dir |= entry.path.ends_with(separator)
+                || entry
+                    .metadata
+                    .is_some_and(|m| m.file_type() == FileType::Dir);
+            listed.size = entry.size;
+            listed.metadata = entry.metadata;
+            listed.target = entry.target;
+            listed.compressed_size = entry.compressed_size;
+            listed.hard_link = entry.hard_link;
+        }
+        let node = self.listed_node(self.root.clone(), None, &root, SpecialFile::Root);
+        self.assemble_listed(node, root, Path::new(""))
+    }
+
+    /// Returns the node for the given listed entry, without its children.
+    fn listed_node(
+        &self,
+        path: PathBuf,
+        label: Option<PathBuf>,
+        listed: &Listed,
+        special: SpecialFile,
+    ) -> DirTree {
+        let is_dir = listed.is_dir || matches!(special, SpecialFile::Root);
+        let mut metadata = listed.metadata.unwrap_or_default();
+        if metadata.mode & S_IFMT == 0 {
+            metadata.mode |= match (is_dir, &listed.target) {
+                (true, _) => S_IFDIR,
+                (false, Some(_)) => S_IFLNK,
+                (false, None) => S_IFREG,
+            };
+        }
+        DirTree {
+            path,
+            special,
+            children: Vec::new(),
+            is_dir,
+            truncated: false,
+            error: None,
+            size: listed.size,
+            total_size: 0,
+            compressed_size: listed.compressed_size,
+            metadata,
+            hash: None,
+            hard_link: (listed.hard_link.as_ref()).map(|link| {
+                (link.split(self.separator.as_str()))
+                    .filter(|c| !c.is_empty() && *c != ".")
+                    .collect()
+            }),
+            mount: None,
+            listed: true,
+            label,
+            display: Arc::clone(&self.display),
+        }
+    }
+
+    /// Completes the given node with the children of the given listed entry, which has the given
+    /// path relative to the root, applying the builder's filters.
+    fn assemble_listed(&self, mut node: DirTree, listed: Listed, relative: &Path) -> DirTree {
+        if node.is_dir
+            && self
+                .max_depth
+                .is_some_and(|max| relative.components().count() >= max)
+        {
+            node.truncated = true;
+            return self.finish(node, Vec::new());
+        }
+        let mut children = Vec::new();
+        for (name, child) in listed.children {
+            let child_relative = relative.join(&name);
+            if self.is_excluded(&child_relative, child.is_dir) {
+                continue;
+            }
+            let path = node.path.join(&name);
+            let label = Some(PathBuf::from(&name));
+            let special = match &child.target {
+                Some(target) => SpecialFile::Symlink(PathBuf::from(target), LinkStatus::Ok),
+                None => SpecialFile::None,
+            };
+            let child_node = self.listed_node(path, label, &child, special);
+            children.push(self.assemble_listed(child_node, child, &child_relative));
+        }
+        self.finish(node, children)
+    }
+}
@@ -0,0 +1,623 @@
+//! Reading the filesystem to build a [DirTree], either on the current thread or on several.
+//!
+//! Both walkers are built from the same three steps, so they produce the same tree: an entry is
+//! read with [DirTreeBuilder::read_entry], the contents of a directory with
+//! [DirTreeBuilder::read_contents], and once every child of a node is complete, the node is
+//! completed with [DirTreeBuilder::finish].
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use super::duplicates::collect_files;
+use super::hash::{Digest, hash_file};
+use super::ignore::IgnoreStack;
+use super::mounts::MountTable;
+use super::sort::SortOrder;
+use super::{DirTree, DirTreeBuilder, EntryMetadata, LinkStatus, SpecialFile, relative_str};
+
+/// Identifies a directory, so that symbolic links which lead back to a directory being read can
+/// be detected.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// Returns the [DirId] of the directory at the given path, whose metadata is given.
+#[cfg(unix)]
+fn dir_id(_path: &Path, metadata: &fs::Metadata) -> io::Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path, _metadata: &fs::Metadata) -> io::Result<DirId> {
+    path.canonicalize()
+}
+
+/// [ScanError] is an error encountered while reading an entry of a [DirTree], as returned by
+/// [DirTreeBuilder::build_tolerant].
+#[derive(Debug)]
+pub struct ScanError {
+    /// The path of the entry which could not be read.
+    pub path: PathBuf,
+    /// The error encountered.
+    pub error: io::Error,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+/// The errors recorded by one thread while reading the filesystem.
+#[derive(Debug, Default)]
+pub(super) struct ReadState {
+    // Whether errors for entries below the root are recorded rather than returned.
+    tolerant: bool,
+    errors: Vec<ScanError>,
+}
+
+impl ReadState {
+    pub(super) fn new(tolerant: bool) -> Self {
+        ReadState {
+            tolerant,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Returns the errors tolerated so far.
+    pub(super) fn into_errors(self) -> Vec<ScanError> {
+        self.errors
+    }
+
+    /// Records the given error for the entry at the given path and returns the given message to
+    /// show alongside the entry, or returns the error if not tolerating errors.
+    fn tolerate(
+        &mut self,
+        path: &Path,
+        error: io::Error,
+        message: &'static str,
+    ) -> io::Result<&'static str> {
+        if !self.tolerant {
+            return Err(error);
+        }
+        self.errors.push(ScanError {
+            path: path.to_path_buf(),
+            error,
+        });
+        Ok(message)
+    }
+}
+
+/// What is needed to read the contents of a directory, which depends on the directories above it.
+#[derive(Debug, Clone, Default)]
+pub(super) struct DirContext {
+    // The path of the directory relative to the root.
+    relative: PathBuf,
+    // The ignore files which apply to the parent of the directory.
+    ignores: Option<IgnoreStack>,
+    // The directories from the root down to this one, when following links.
+    ancestors: Vec<DirId>,
+    // The device of the directory, which its children are on unless they are mount points.
+    device: u64,
+    // The mounts, and the absolute path of the directory, when annotating mount points.
+    mounts: Option<(Arc<MountTable>, PathBuf)>,
+}
+
+impl DirContext {
+    /// Returns the context in which the root of the tree is read.
+    pub(super) fn root(builder: &DirTreeBuilder) -> Self {
+        DirContext {
+            relative: PathBuf::new(),
+            ignores: builder
+                .gitignore
+                .then(|| IgnoreStack::for_root(&builder.root)),
+            ancestors: Vec::new(),
+            device: 0,
+            // Without a table of mounts, mount points are found by their devices alone.
+            mounts: builder.mounts.then(|| {
+                let table = (builder.mount_table.clone())
+                    .unwrap_or_else(|| Arc::new(MountTable::read().unwrap_or_default()));
+                (table, PathBuf::new())
+            }),
+        }
+    }
+}
+
+/// A node without its children, along with the context for reading its contents if it is a
+/// directory which should be read.
+pub(super) type Entry = (DirTree, Option<DirContext>);
+
+/// A child of a directory read by a thread, whose contents may still be being read.
+enum Pending {
+    /// The child has no contents to read.
+    Read(Box<DirTree>),
+    /// The child is being read by the job with this id.
+    Job(usize),
+}
+
+/// For each job, the directory it read, with its error set, and the children of the directory.
+type Done = HashMap<usize, (DirTree, Vec<Pending>)>;
+
+impl DirTreeBuilder {
+    /// Reads the whole tree on the number of threads set by [DirTreeBuilder::threads], returning
+    /// it along with the errors tolerated.
+    pub(super) fn scan(&self, tolerant: bool) -> io::Result<(DirTree, Vec<ScanError>)> {
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let mut state = ReadState::new(tolerant);
+        let parent = DirContext::root(self);
+        let root = self.read_entry(self.root.clone(), PathBuf::new(), true, &parent, &mut state)?;
+        let mut tree = if threads <= 1 {
+            self.read_below(root, &mut state)?
+        } else {
+            self.read_below_parallel(root, threads, &mut state)?
+        };
+        if self.hard_links {
+            let root = tree.path.clone();
+            self.link_hard_links(&mut tree, &root, &mut HashMap::new());
+        }
+        if self.hash {
+            hash_same_sizes(&mut tree, threads, &mut state)?;
+        }
+        Ok((tree, state.errors))
+    }
+
+    /// Reads everything below the given entry on the current thread, returning the complete node.
+    fn read_below(&self, entry: Entry, state: &mut ReadState) -> io::Result<DirTree> {
+        let (mut node, context) = entry;
+        let mut children = Vec::new();
+        if let Some(context) = context {
+            for child in self.read_contents(&mut node, context, state)? {
+                children.push(self.read_below(child, state)?);
+            }
+        }
+        Ok(self.finish(node, children))
+    }
+
+    /// Reads everything below the given entry using a queue of directories shared by the given
+    /// number of threads, returning the complete node.
+    ///
+    /// Each thread takes a directory from the queue, reads its contents, and adds the directories
+    /// among them to the queue. The nodes are then assembled into a tree once every directory has
+    /// been read. If an error is not tolerated, the threads stop as soon as they can, and one of
+    /// the errors is returned.
+    fn read_below_parallel(
+        &self,
+        root: Entry,
+        threads: usize,
+        state: &mut ReadState,
+    ) -> io::Result<DirTree> {
+        struct Queue {
+            // Each directory waiting to be read, along with the id of its job.
+            waiting: VecDeque<(usize, DirTree, DirContext)>,
+            // The number of directories being read.
+            active: usize,
+            next_id: usize,
+            failure: Option<io::Error>,
+        }
+
+        let (root, context) = root;
+        let Some(context) = context else {
+            return Ok(self.finish(root, Vec::new()));
+        };
+        let queue = Mutex::new(Queue {
+            waiting: VecDeque::from([(0, root, context)]),
+            active: 0,
+            next_id: 1,
+            failure: None,
+        });
+        let changed = Condvar::new();
+        let done: Mutex<Done> = Mutex::default();
+        let errors = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    let mut local = ReadState::new(state.tolerant);
+                    loop {
+                        let mut guard = queue.lock().unwrap();
+                        while guard.waiting.is_empty() && guard.active > 0 {
+                            guard = changed.wait(guard).unwrap();
+                        }
+                        if guard.failure.is_some() {
+                            break;
+                        }
+                        let Some((id, mut node, context)) = guard.waiting.pop_front() else {
+                            break;
+                        };
+                        guard.active += 1;
+                        drop(guard);
+
+                        let result = self.read_contents(&mut node, context, &mut local);
+
+                        let mut guard = queue.lock().unwrap();
+                        guard.active -= 1;
+                        match result {
+                            Ok(children) => {
+                                let mut pending = Vec::with_capacity(children.len());
+                                for (child, context) in children {
+                                    let Some(context) = context else {
+                                        pending.push(Pending::Read(Box::new(child)));
+                                        continue;
+                                    };
+                                    let child_id = guard.next_id;
+                                    guard.next_id += 1;
+                                    guard.waiting.push_back((child_id, child, context));
+                                    pending.push(Pending::Job(child_id));
+                                }
+                                done.lock().unwrap().insert(id, (node, pending));
+                            }
+                            Err(e) => {
+                                guard.failure.get_or_insert(e);
+                                guard.waiting.clear();
+                            }
+                        }
+                        changed.notify_all();
+                    }
+                    errors.lock().unwrap().append(&mut local.errors);
+                });
+            }
+        });
+
+        if let Some(e) = queue.into_inner().unwrap().failure {
+            return Err(e);
+        }
+        state.errors.append(&mut errors.into_inner().unwrap());
+        let mut done = done.into_inner().unwrap();
+        Ok(self.assemble(0, &mut done))
+    }
+
+    /// Builds the complete node read by the job with the given id from the results of every job.
+    fn assemble(&self, id: usize, done: &mut Done) -> DirTree {
+        let (node, pending) = done.remove(&id).unwrap();
+        let children = pending
+            .into_iter()
+            .map(|child| match child {
+                Pending::Read(child) => self.finish(*child, Vec::new()),
+                Pending::Job(child_id) => self.assemble(child_id, done),
+            })
+            .collect();
+        self.finish(node, children)
+    }
+
+    /// Reads the entry at the given path, which has the given path relative to the root, without
+    /// reading its contents. If it is a directory whose contents should be read, also returns the
+    /// context for reading them.
+    pub(super) fn read_entry(
+        &self,
+        path: PathBuf,
+        relative: PathBuf,
+        is_root: bool,
+        parent: &DirContext,
+        state: &mut ReadState,
+    ) -> io::Result<Entry> {
+        let metadata = match path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(e) if is_root => return Err(e),
+            Err(e) => {
+                let message = state.tolerate(&path, e, "error reading entry")?;
+                return Ok((self.unreadable(path, message), None));
+            }
+        };
+        // A problem with the entry which did not stop it from being read entirely.
+        let mut error = None;
+        // Only traverse symlinks if the path is the root of the dir tree, unless following links.
+        let follow = is_root || self.follow_links;
+        // Whether the entry is a symbolic link whose target does not exist.
+        let mut broken = false;
+        // The metadata of the target of a symbolic link which is followed, or `None` if it could
+        // not be read.
+        let target = match metadata.is_symlink() && follow {
+            true => match path.metadata() {
+                Ok(target) => Some(target),
+                Err(e) if is_root => return Err(e),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    broken = true;
+                    None
+                }
+                // Such as a loop of links, or a target in a directory which cannot be searched.
+                Err(_) => {
+                    error = Some("error reading link target");
+                    None
+                }
+            },
+            false => None,
+        };
+        if metadata.is_symlink() && !follow {
+            // Check that the target exists without following it any further.
+            broken = path.read_link().is_ok_and(|link| {
+                let target = path.parent().unwrap_or(Path::new("")).join(link);
+                target
+                    .symlink_metadata()
+                    .is_err_and(|e| e.kind() == io::ErrorKind::NotFound)
+            });
+        }
+        let is_dir = metadata.is_dir() || target.as_ref().is_some_and(|t| t.is_dir());
+        let mut link_status = match broken {
+            true => LinkStatus::Broken,
+            false => LinkStatus::Ok,
+        };
+        let id = match is_dir && self.follow_links {
+            true => match dir_id(&path, target.as_ref().unwrap_or(&metadata)) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    error = Some(state.tolerate(&path, e, "error opening dir")?);
+                    None
+                }
+            },
+            false => None,
+        };
+        if id.as_ref().is_some_and(|id| parent.ancestors.contains(id)) {
+            link_status = LinkStatus::Recursive;
+        }
+        let entry_metadata = EntryMetadata::from_fs(target.as_ref().unwrap_or(&metadata));
+        // Whether the entry is a directory on another filesystem from its parent.
+        let crosses_device = !is_root && is_dir && entry_metadata.device != parent.device;
+        let absolute = parent.mounts.as_ref().map(|(table, parent_path)| {
+            let absolute = match is_root || is_dir && metadata.is_symlink() {
+                true => path.canonicalize().unwrap_or_else(|_| path.clone()),
+                false => parent_path.join(path.file_name().unwrap_or_default()),
+            };
+            (Arc::clone(table), absolute)
+        });
+        let mount = absolute.as_ref().and_then(|(table, absolute)| {
+            match (table.get(absolute), table.mounts().is_empty()) {
+                (Some(mount), _) => Some(mount.fs_type.clone()),
+                (None, true) if crosses_device => Some(String::from("?")),
+                (None, _) => None,
+            }
+        });
+        let readable = is_dir
+            && link_status != LinkStatus::Recursive
+            && error.is_none()
+            && !(self.one_filesystem && crosses_device);
+        let truncated = readable
+            && self
+                .max_depth
+                .is_some_and(|max| relative.components().count() >= max);
+        let context = (readable && !truncated).then(|| {
+            let mut ancestors = parent.ancestors.clone();
+            ancestors.extend(id);
+            DirContext {
+                relative,
+                ignores: parent.ignores.clone(),
+                ancestors,
+                device: entry_metadata.device,
+                mounts: absolute,
+            }
+        });
+        let special = match (is_root, metadata.is_symlink()) {
+            (true, _) => SpecialFile::Root,
+            (_, true) => match path.read_link() {
+                Ok(link) => SpecialFile::Symlink(link, link_status),
+                Err(e) => {
+                    error = Some(state.tolerate(&path, e, "error reading link")?);
+                    SpecialFile::Symlink(PathBuf::new(), link_status)
+                }
+            },
+            _ => SpecialFile::None,
+        };
+        let node = DirTree {
+            path,
+            special,
+            children: Vec::new(),
+            is_dir,
+            truncated,
+            error,
+            size: metadata.len(),
+            total_size: 0,
+            compressed_size: None,
+            metadata: EntryMetadata::from_fs(&metadata),
+            hash: None,
+            hard_link: None,
+            mount,
+            listed: false,
+            label: None,
+            display: Arc::clone(&self.display),
+        };
+        Ok((node, context))
+    }
+
+    /// Reads the contents of the directory associated with the given node, which are not filtered
+    /// out, without reading their contents in turn. The error of the node is set if the contents
+    /// could only be read in part.
+    pub(super) fn read_contents(
+        &self,
+        node: &mut DirTree,
+        mut context: DirContext,
+        state: &mut ReadState,
+    ) -> io::Result<Vec<Entry>> {
+        let relative = &context.relative;
+        if let Some(ignores) = &mut context.ignores {
+            ignores.enter(&node.path, &relative_str(relative));
+        }
+        let entries = match fs::read_dir(&node.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                node.error = Some(state.tolerate(&node.path, e, "error opening dir")?);
+                return Ok(Vec::new());
+            }
+        };
+        let mut children = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    node.error = Some(state.tolerate(&node.path, e, "error reading dir")?);
+                    break;
+                }
+            };
+            let child_relative = relative.join(entry.file_name());
+            // If the type cannot be read, the error resurfaces when reading the entry.
+            let is_dir = entry.file_type().is_ok_and(|file_type| {
+                file_type.is_dir()
+                    || self.follow_links
+                        && file_type.is_symlink()
+                        && entry.path().metadata().is_ok_and(|m| m.is_dir())
+            });
+            if self.is_excluded(&child_relative, is_dir)
+                || context
+                    .ignores
+                    .as_ref()
+                    .is_some_and(|i| i.is_ignored(&relative_str(&child_relative), is_dir))
+            {
+                continue;
+            }
+            children.push(self.read_entry(entry.path(), child_relative, false, &context, state)?);
+        }
+        Ok(children)
+    }
+
+    /// Completes the given node with its given complete children, pruning and sorting them and
+    /// adding up their sizes, then replacing directories with their contents if only showing
+    /// files.
+    pub(super) fn finish(&self, mut node: DirTree, mut children: Vec<DirTree>) -> DirTree {
+        if self.prune {
+            children.retain(|child| {
+                !child.is_dir
+                    || !child.children.is_empty()
+                    || child.truncated
+                    || child.error.is_some()
+                    || child.is_recursive_link()
+            });
+        }
+        self.sort_children(&mut children, |child| child);
+        node.total_size = node.size + children.iter().map(|c| c.total_size).sum::<u64>();
+        if self.files_only {
+            children = children.into_iter().flat_map(flatten).collect();
+        }
+        node.children = children;
+        node
+    }
+
+    /// Marks every entry at or below the given node which is a later hard link to a file already
+    /// seen, in the order the tree is shown, with the path relative to the root of the first, and
+    /// leaves it out of the total sizes of the directories above it, sorting their children again
+    /// if they are sorted by size. Returns the number of bytes left out.
+    fn link_hard_links(
+        &self,
+        node: &mut DirTree,
+        root: &Path,
+        first: &mut HashMap<(u64, u64), PathBuf>,
+    ) -> u64 {
+        let metadata = node.metadata;
+        if !node.is_dir && metadata.links > 1 {
+            let relative = node.path.strip_prefix(root).unwrap_or(&node.path);
+            match first.get(&(metadata.device, metadata.inode)) {
+                Some(path) => {
+                    node.hard_link = Some(path.clone());
+                    return std::mem::take(&mut node.total_size);
+                }
+                None => {
+                    first.insert((metadata.device, metadata.inode), relative.to_path_buf());
+                }
+            }
+        }
+        let removed = (node.children.iter_mut())
+            .map(|child| self.link_hard_links(child, root, first))
+            .sum();
+        node.total_size -= removed;
+        // The children of directories are already flattened if only showing files, and sorting
+        // them again would mix up the directories they came from.
+        if removed > 0 && self.sort == SortOrder::Size && !self.files_only {
+            self.sort_children(&mut node.children, |child| child);
+        }
+        removed
+    }
+
+    /// Returns a node for the entry at the given path, whose metadata could not be read.
+    fn unreadable(&self, path: PathBuf, error: &'static str) -> DirTree {
+        DirTree {
+            path,
+            special: SpecialFile::None,
+            children: Vec::new(),
+            is_dir: false,
+            truncated: false,
+            error: Some(error),
+            size: 0,
+            total_size: 0,
+            compressed_size: None,
+            metadata: EntryMetadata::default(),
+            hash: None,
+            hard_link: None,
+            mount: None,
+            listed: false,
+            label: None,
+            display: Arc::clone(&self.display),
+        }
+    }
+}
+
+/// Returns the given node if it is not a directory, or otherwise its children, which have already
+/// been flattened, labelled with their paths relative to its parent.
+fn flatten(node: DirTree) -> Vec<DirTree> {
+    if !node.is_dir {
+        return vec![node];
+    }
+    let name = match node.label {
+        Some(label) => label,
+        None => PathBuf::from(node.path.file_name().unwrap_or_default()),
+    };
+    let mut children = node.children;
+    for child in &mut children {
+        let label = match child.label.take() {
+            Some(label) => label,
+            None => PathBuf::from(child.path.file_name().unwrap_or_default()),
+        };
+        child.label = Some(name.join(label));
+    }
+    children
+}
+
+/// Hashes the contents of every file in the tree which [DirTree::duplicates] would compare with
+/// another, on the given number of threads, recording any errors in `state`.
+fn hash_same_sizes(tree: &mut DirTree, threads: usize, state: &mut ReadState) -> io::Result<()> {
+    let mut by_size = BTreeMap::new();
+    collect_files(tree, &mut by_size);
+    let paths: Vec<PathBuf> = (by_size.into_values())
+        .filter(|files| files.len() > 1)
+        .flatten()
+        .map(|file| file.path.clone())
+        .collect();
+    let next = Mutex::new(paths.iter());
+    let hashes = Mutex::new(HashMap::new());
+    thread::scope(|scope| {
+        for _ in 0..threads.min(paths.len()) {
+            scope.spawn(|| {
+                loop {
+                    let Some(path) = next.lock().unwrap().next() else {
+                        break;
+                    };
+                    let hash = hash_file(path);
+                    hashes.lock().unwrap().insert(path.clone(), hash);
+                }
+            });
+        }
+    });
+    set_hashes(tree, &mut hashes.into_inner().unwrap(), state)
+}
+
+/// Sets the hash of every entry at or below the given node which was hashed, in the order the
+/// tree is shown, or its error if it could not be hashed.
+fn set_hashes(
+    node: &mut DirTree,
+    hashes: &mut HashMap<PathBuf, io::Result<Digest>>,
+    state: &mut ReadState,
+) -> io::Result<()> {
+    match hashes.remove(&node.path) {
+        Some(Ok(hash)) => node.hash = Some(hash),
+        Some(Err(e)) => node.error = Some(state.tolerate(&node.path, e, "error hashing file")?),
+        None => (),
+    }
+    (node.children.iter_mut()).try_for_each(|child| set_hashes(child, hashes, state))
+}
@@ -0,0 +1,477 @@
+//! Recording the entries of a [DirTree] to compare them with a later reading of the filesystem.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::hash::hash_file;
+use super::metadata::FileType;
+use super::{DirTree, relative_str};
+use crate::node::Node;
+
+/// The first line of every snapshot, which identifies the format and its version.
+const HEADER: &str = "# simple-tree snapshot 1";
+
+/// [EntryKind] is the type of an entry in a [Snapshot].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+    /// Anything else, such as a device or a socket.
+    Other,
+}
+
+impl EntryKind {
+    fn code(self) -> char {
+        match self {
+            EntryKind::Dir => 'd',
+            EntryKind::File => 'f',
+            EntryKind::Symlink => 'l',
+            EntryKind::Other => 'o',
+        }
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EntryKind::Dir => "directory",
+            EntryKind::File => "file",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Other => "other",
+        })
+    }
+}
+
+/// [SnapshotEntry] is what a [Snapshot] records about each entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    pub kind: EntryKind,
+    /// The size of the entry itself, in bytes.
+    pub size: u64,
+    /// The time the entry was last modified, since the unix epoch, if known.
+    pub modified: Option<Duration>,
+    /// A hash of the contents of the entry, in hexadecimal, if recorded.
+    pub hash: Option<String>,
+}
+
+/// [Snapshot] records the entries of a [DirTree], so that they can be written to a file and
+/// compared with a later reading of the filesystem.
+///
+/// Each entry below the root is recorded by its path relative to the root, separated by `/`. The
+/// root itself is not recorded, so snapshots of different directories can be compared.
+///
+/// # Format
+///
+/// Snapshots are written as text. The first line is `# simple-tree snapshot 1`, and each
+/// following line records one entry as five fields separated by tabs:
+///
+/// 1. The kind of the entry: `d` for a directory, `f` for a file, `l` for a symbolic link or
+///    `o` for anything else.
+/// 2. The size in bytes.
+/// 3. The modification time as seconds and nanoseconds since the unix epoch, such as
+///    `1700000000.250000000`, or `-` if unknown.
+/// 4. The hash of the contents in hexadecimal, or `-` if not recorded.
+/// 5. The path, where `\`, tabs and line breaks are escaped as `\\`, `\t`, `\n` and `\r`. Names
+///    which are not valid unicode are recorded lossily.
+///
+/// Entries are sorted by path, so that the same tree is always written the same way. Blank lines
+/// and other lines beginning with `#` are ignored when reading.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+/// use std::fs;
+/// use simple_tree::implementations::DirTree;
+/// use simple_tree::implementations::dir_tree::snapshot::Snapshot;
+///
+/// let tmpdir = env::temp_dir().join("test-dir-tree-snapshot");
+/// fs::create_dir_all(tmpdir.join("bin")).unwrap();
+/// fs::write(tmpdir.join("bin/app"), "v1").unwrap();
+/// fs::write(tmpdir.join("README"), "hello").unwrap();
+/// fs::write(tmpdir.join("old.txt"), "").unwrap();
+///
+/// let before = Snapshot::new(&DirTree::new(&tmpdir).unwrap());
+/// let mut file = Vec::new();
+/// before.write(&mut file).unwrap();
+/// let before = Snapshot::read(&file[..]).unwrap();
+///
+/// fs::write(tmpdir.join("bin/app"), "version 2").unwrap();
+/// fs::write(tmpdir.join("bin/app.map"), "").unwrap();
+/// fs::remove_file(tmpdir.join("old.txt")).unwrap();
+/// fs::remove_file(tmpdir.join("README")).unwrap();
+/// fs::create_dir(tmpdir.join("README")).unwrap();
+///
+/// let after = Snapshot::new(&DirTree::new(&tmpdir).unwrap());
+/// fs::remove_dir_all(&tmpdir).unwrap();
+///
+/// let diff = before.diff(&after);
+/// assert_eq!(format!("{}", diff.tree("release")),
+/// "release
+/// ├── README  [file -> directory]
+/// ├── bin
+/// │   ├── app  [modified]
+/// │   └── app.map  [added]
+/// └── old.txt  [removed]");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    entries: BTreeMap<String, SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// Returns a snapshot of the entries below the root of the given tree, recording the hashes
+    /// of the files which were hashed if the tree was built with
+    /// [DirTreeBuilder::hash](super::DirTreeBuilder::hash).
+    pub fn new(tree: &DirTree) -> Self {
+        Self::with_hashes(tree, |node| Ok(node.hash.map(|hash| hash.to_string()))).unwrap()
+    }
+
+    /// Returns a snapshot of the entries below the root of the given tree, recording the hash of
+    /// every regular file, so that files are compared by their contents alone. Files which were
+    /// not hashed while building the tree are hashed now, unless they were listed rather than
+    /// read from the filesystem.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if a file cannot be read, in which case the error includes its
+    /// path.
+    pub fn hashed(tree: &DirTree) -> io::Result<Self> {
+        Self::with_hashes(tree, |node| match node.hash {
+            Some(hash) => Ok(Some(hash.to_string())),
+            None if node.metadata.is_file() && !node.listed => hash_file(&node.path)
+                .map(|hash| Some(hash.to_string()))
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", node.path.display(), e))),
+            None => Ok(None),
+        })
+    }
+
+    /// Returns a snapshot of the entries below the root of the given tree, where the hash of each
+    /// entry is returned by `hash`, which is given the node of the entry.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if `hash` returns an error.
+    pub fn with_hashes<F>(tree: &DirTree, mut hash: F) -> io::Result<Self>
+    where
+        F: FnMut(&DirTree) -> io::Result<Option<String>>,
+    {
+        let mut snapshot = Snapshot::default();
+        for child in tree.children() {
+            snapshot.record(&tree.path, child, &mut hash)?;
+        }
+        Ok(snapshot)
+    }
+
+    fn record<F>(&mut self, root: &Path, node: &DirTree, hash: &mut F) -> io::Result<()>
+    where
+        F: FnMut(&DirTree) -> io::Result<Option<String>>,
+    {
+        let relative = relative_str(node.path.strip_prefix(root).unwrap_or(&node.path));
+        let metadata = &node.metadata;
+        let kind = match metadata.file_type() {
+            FileType::Dir => EntryKind::Dir,
+            FileType::File => EntryKind::File,
+            FileType::Symlink => EntryKind::Symlink,
+            _ => EntryKind::Other,
+        };
+        let entry = SnapshotEntry {
+            kind,
+            size: node.size,
+            modified: metadata
+                .modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok()),
+            hash: hash(node)?,
+        };
+        self.entries.insert(relative, entry);
+        for child in node.children() {
+            self.record(root, child, hash)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the entries of the snapshot, sorted by path.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &SnapshotEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_str(), entry))
+    }
+
+    /// Returns the entry with the given path relative to the root, separated by `/`.
+    pub fn get(&self, path: &str) -> Option<&SnapshotEntry> {
+        self.entries.get(path)
+    }
+
+    /// Writes the snapshot in the format described above.
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{}", HEADER)?;
+        for (path, entry) in &self.entries {
+            let modified = match entry.modified {
+                Some(time) => format!("{}.{:09}", time.as_secs(), time.subsec_nanos()),
+                None => String::from("-"),
+            };
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.kind.code(),
+                entry.size,
+                modified,
+                entry.hash.as_deref().unwrap_or("-"),
+                escape(path)
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Reads a snapshot in the format described above.
+    ///
+    /// # Errors
+    ///
+    /// This function will error with [io::ErrorKind::InvalidData] if the input is not a snapshot,
+    /// or is a snapshot in a later version of the format, or any line is malformed.
+    pub fn read<R: BufRead>(input: R) -> io::Result<Self> {
+        let invalid = |line: usize, message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} of snapshot: {}", line, message),
+            )
+        };
+        let mut lines = input.lines();
+        match lines.next().transpose()? {
+            Some(header) if header == HEADER => (),
+            Some(header) if header.starts_with("# simple-tree snapshot ") => {
+                return Err(invalid(1, "unsupported version"));
+            }
+            _ => return Err(invalid(1, "not a snapshot")),
+        }
+        let mut snapshot = Snapshot::default();
+        for (i, line) in lines.enumerate() {
+            let (number, line) = (i + 2, line?);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.splitn(5, '\t').collect();
+            let [kind, size, modified, hash, path] = fields[..] else {
+                return Err(invalid(number, "expected 5 fields"));
+            };
+            let kind = match kind {
+                "d" => EntryKind::Dir,
+                "f" => EntryKind::File,
+                "l" => EntryKind::Symlink,
+                "o" => EntryKind::Other,
+                _ => return Err(invalid(number, "unknown kind")),
+            };
+            let size = size.parse().map_err(|_| invalid(number, "invalid size"))?;
+            let modified = match modified {
+                "-" => None,
+                _ => Some(parse_time(modified).ok_or_else(|| invalid(number, "invalid time"))?),
+            };
+            let hash = match hash {
+                "-" => None,
+                _ => Some(hash.to_string()),
+            };
+            let path = unescape(path).ok_or_else(|| invalid(number, "invalid escape"))?;
+            let entry = SnapshotEntry {
+                kind,
+                size,
+                modified,
+                hash,
+            };
+            snapshot.entries.insert(path, entry);
+        }
+        Ok(snapshot)
+    }
+
+    /// Compares this snapshot with a later one, returning what differs.
+    ///
+    /// An entry is modified if it has the same kind in both snapshots but its contents differ.
+    /// If both snapshots have a hash for the entry, only the hashes are compared, so rebuilding
+    /// a file with the same contents does not count. Otherwise, its size and modification time
+    /// are compared. Directories are never modified, since their contents are compared instead.
+    pub fn diff(&self, later: &Snapshot) -> SnapshotDiff {
+        let mut changes = BTreeMap::new();
+        for (path, before) in &self.entries {
+            let change = match later.entries.get(path) {
+                None => Some(Difference::Removed),
+                Some(after) if after.kind != before.kind => {
+                    Some(Difference::TypeChanged(before.kind, after.kind))
+                }
+                Some(_) if before.kind == EntryKind::Dir => None,
+                Some(after) => {
+                    let modified = match (&before.hash, &after.hash) {
+                        (Some(a), Some(b)) => a != b,
+                        _ => before.size != after.size || before.modified != after.modified,
+                    };
+                    modified.then_some(Difference::Modified)
+                }
+            };
+            if let Some(change) = change {
+                changes.insert(path.clone(), change);
+            }
+        }
+        for path in later.entries.keys() {
+            if !self.entries.contains_key(path) {
+                changes.insert(path.clone(), Difference::Added);
+            }
+        }
+        SnapshotDiff { changes }
+    }
+}
+
+/// Parses a time written as seconds and nanoseconds since the unix epoch.
+fn parse_time(s: &str) -> Option<Duration> {
+    let (secs, nanos) = s.split_once('.').unwrap_or((s, "0"));
+    let nanos: u32 = nanos.parse().ok()?;
+    (nanos < 1_000_000_000).then_some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+fn escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+/// [Difference] is how an entry differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    /// The entry is only in the later snapshot.
+    Added,
+    /// The entry is only in the earlier snapshot.
+    Removed,
+    /// The contents of the entry changed.
+    Modified,
+    /// The entry changed from the first kind to the second.
+    TypeChanged(EntryKind, EntryKind),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Added => f.write_str("added"),
+            Difference::Removed => f.write_str("removed"),
+            Difference::Modified => f.write_str("modified"),
+            Difference::TypeChanged(before, after) => write!(f, "{} -> {}", before, after),
+        }
+    }
+}
+
+/// [SnapshotDiff] is what differs between two snapshots, as returned by [Snapshot::diff].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    changes: BTreeMap<String, Difference>,
+}
+
+impl SnapshotDiff {
+    /// Returns whether the snapshots are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the path of each entry which differs and how, sorted by path.
+    pub fn changes(&self) -> impl Iterator<Item = (&str, Difference)> {
+        self.changes
+            .iter()
+            .map(|(path, change)| (path.as_str(), *change))
+    }
+
+    /// Returns a tree of the entries which differ, along with the directories containing them,
+    /// where the root has the given name.
+    pub fn tree(&self, root: &str) -> DiffTree {
+        let mut tree = DiffTree {
+            name: root.to_string(),
+            difference: None,
+            children: Vec::new(),
+        };
+        for (path, change) in &self.changes {
+            let mut node = &mut tree;
+            for name in path.split('/') {
+                // Keep the children sorted by name, rather than by the paths below them.
+                let i = match node
+                    .children
+                    .binary_search_by(|c| c.name.as_str().cmp(name))
+                {
+                    Ok(i) => i,
+                    Err(i) => {
+                        let child = DiffTree {
+                            name: name.to_string(),
+                            difference: None,
+                            children: Vec::new(),
+                        };
+                        node.children.insert(i, child);
+                        i
+                    }
+                };
+                node = &mut node.children[i];
+            }
+            node.difference = Some(*change);
+        }
+        tree
+    }
+}
+
+/// [DiffTree] shows what differs between two snapshots as a tree, as returned by
+/// [SnapshotDiff::tree]. Each entry which differs is followed by how, such as `[added]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffTree {
+    name: String,
+    difference: Option<Difference>,
+    children: Vec<DiffTree>,
+}
+
+impl DiffTree {
+    /// Returns how the entry differs, or `None` if it only contains entries which differ.
+    pub fn difference(&self) -> Option<Difference> {
+        self.difference
+    }
+}
+
+impl Node for DiffTree {
+    fn value(&self) -> impl fmt::Display {
+        match self.difference {
+            Some(difference) => format!("{}  [{}]", self.name, difference),
+            None => self.name.clone(),
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter()
+    }
+}
+
+impl fmt::Display for DiffTree {
+    /// Format using the default [Node::fmt] implementation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Node::fmt(self, f)
+    }
+}
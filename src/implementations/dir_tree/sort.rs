@@ -0,0 +1,151 @@
+//! The orders in which the children of each directory of a [DirTree] can be listed.
+
+use std::cmp::{Ordering, Reverse};
+use std::ffi::OsStr;
+
+use super::{DirTree, DirTreeBuilder};
+
+/// [SortOrder] defines the order of the children of each directory in a [DirTree].
+///
+/// Every order can be reversed with [DirTreeBuilder::reverse](super::DirTreeBuilder::reverse),
+/// and combined with listing directories first with
+/// [DirTreeBuilder::dirs_first](super::DirTreeBuilder::dirs_first).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Sort entries by name, comparing the bytes of their paths.
+    #[default]
+    Name,
+    /// Sort entries by name, comparing runs of digits by their numeric value, so that `file2`
+    /// comes before `file10`, like `tree -v`. See [natural_cmp].
+    Natural,
+    /// Sort entries by name, ignoring case. Names which differ only by case are sorted by their
+    /// bytes.
+    CaseInsensitive,
+    /// Sort entries by extension, where entries without an extension come first. Entries with the
+    /// same extension are sorted by name.
+    Extension,
+    /// Sort entries by the time they were last modified, from oldest to newest, like `tree -t`.
+    /// Entries modified at the same time are sorted by name.
+    Modified,
+    /// Sort entries by size, from largest to smallest, where the size of a directory is the total
+    /// size of it and its contents. Entries of the same size are sorted by name.
+    Size,
+    /// Leave entries in the order the operating system lists them in, like `tree -U`.
+    Unsorted,
+}
+
+/// Compares two strings as a person would, treating each run of ASCII digits as a number, so
+/// that `file2` comes before `file10`. Strings which compare equal this way, such as `a01` and
+/// `a1`, are compared by their bytes.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use simple_tree::implementations::dir_tree::sort::natural_cmp;
+///
+/// assert_eq!(natural_cmp("file2", "file10"), Ordering::Less);
+/// assert_eq!(natural_cmp("v1.10.0", "v1.9.3"), Ordering::Greater);
+/// assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+/// assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+/// assert_eq!(natural_cmp("abc", "abd"), Ordering::Less);
+///
+/// let mut names = ["img12.png", "img10.png", "IMG3.png", "img2.png", "img1.png"];
+/// names.sort_by(|a, b| natural_cmp(a, b));
+/// assert_eq!(names, ["IMG3.png", "img1.png", "img2.png", "img10.png", "img12.png"]);
+/// ```
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    while !a_rest.is_empty() && !b_rest.is_empty() {
+        let (a_chunk, a_next) = split_chunk(a_rest);
+        let (b_chunk, b_next) = split_chunk(b_rest);
+        let is_number = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if is_number(a_chunk) && is_number(b_chunk) {
+            // Without leading zeros, a longer run of digits is a larger number.
+            let a_digits = a_chunk.trim_start_matches('0');
+            let b_digits = b_chunk.trim_start_matches('0');
+            a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits))
+        } else {
+            a_chunk.cmp(b_chunk)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        (a_rest, b_rest) = (a_next, b_next);
+    }
+    a_rest
+        .is_empty()
+        .cmp(&b_rest.is_empty())
+        .reverse()
+        .then_with(|| a.cmp(b))
+}
+
+/// Splits the given non-empty string after its leading run of either digits or non-digits.
+fn split_chunk(s: &str) -> (&str, &str) {
+    let digits = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Returns the name of the entry of the given node, or an empty name for a root without one.
+fn name(node: &DirTree) -> &OsStr {
+    node.path.file_name().unwrap_or_default()
+}
+
+impl DirTreeBuilder {
+    /// Sorts the given siblings, each of which holds a node returned by `node`, in the order set
+    /// by [DirTreeBuilder::sort], [DirTreeBuilder::reverse] and [DirTreeBuilder::dirs_first].
+    pub(super) fn sort_children<T>(&self, children: &mut [T], node: impl Fn(&T) -> &DirTree) {
+        let (order, reverse) = (self.sort, self.reverse);
+        let compare = |a: &DirTree, b: &DirTree| -> Ordering {
+            let by_name = || a.path.cmp(&b.path);
+            match order {
+                SortOrder::Name => by_name(),
+                SortOrder::Natural => {
+                    natural_cmp(&name(a).to_string_lossy(), &name(b).to_string_lossy())
+                }
+                SortOrder::CaseInsensitive => {
+                    let a_name = name(a).to_string_lossy().to_lowercase();
+                    let b_name = name(b).to_string_lossy().to_lowercase();
+                    a_name.cmp(&b_name).then_with(by_name)
+                }
+                SortOrder::Extension => a
+                    .path
+                    .extension()
+                    .cmp(&b.path.extension())
+                    .then_with(by_name),
+                SortOrder::Modified => a
+                    .metadata
+                    .modified
+                    .cmp(&b.metadata.modified)
+                    .then_with(by_name),
+                SortOrder::Size => Reverse(a.total_size)
+                    .cmp(&Reverse(b.total_size))
+                    .then_with(by_name),
+                SortOrder::Unsorted => Ordering::Equal,
+            }
+        };
+        if order == SortOrder::Unsorted && reverse {
+            children.reverse();
+        }
+        // The sort is stable, so unsorted entries keep their order, apart from directories moving
+        // first.
+        children.sort_by(|a, b| {
+            let (a, b) = (node(a), node(b));
+            let dirs = if self.dirs_first {
+                b.is_dir.cmp(&a.is_dir)
+            } else {
+                Ordering::Equal
+            };
+            dirs.then_with(|| match reverse {
+                true => compare(b, a),
+                false => compare(a, b),
+            })
+        });
+    }
+}
@@ -0,0 +1,122 @@
+//! Writing a [DirTree](super::DirTree) as the filesystem is read, without building it in memory.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::scan::{DirContext, Entry, ReadState, ScanError};
+use super::{DirTreeBuilder, SortOrder};
+use crate::node::{BLANK, BRANCH, LAST_BRANCH, Node, PIPE};
+
+impl DirTreeBuilder {
+    /// Reads the directory tree according to the builder's options and writes it to `out` as it
+    /// goes, returning every error encountered like [Self::build_tolerant].
+    ///
+    /// The output is exactly the same as displaying the tree returned by [Self::build_tolerant],
+    /// followed by a newline. Each directory is written as soon as its own contents have been
+    /// read, and only the contents of the directories from the root down to the one being read
+    /// are held in memory, however large the tree is. The output is flushed before reading each
+    /// directory, so everything before it appears while it is being read.
+    ///
+    /// [Self::threads] has no effect. Showing the total sizes of directories (see
+    /// [DisplayOptions::du](super::DisplayOptions)), or sorting by [SortOrder::Size], needs the
+    /// size of everything below a directory before it can be written, so in those cases the whole
+    /// tree is built in memory first, and then written. The same goes for [Self::prune],
+    /// [Self::files_only], [Self::hard_links] and [Self::hash], as described by each of them.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the root itself cannot be read, or if writing to `out` fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::dir_tree::{DirTree, DisplayOptions, SizeFormat, SortOrder};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-stream");
+    /// fs::create_dir_all(tmpdir.join("a/b")).unwrap();
+    /// fs::create_dir_all(tmpdir.join("c")).unwrap();
+    /// fs::write(tmpdir.join("a/b/file"), vec![0; 3000]).unwrap();
+    /// fs::write(tmpdir.join("a/file"), "").unwrap();
+    /// fs::write(tmpdir.join("c/file"), vec![0; 2000]).unwrap();
+    ///
+    /// let builder = DirTree::builder(&tmpdir);
+    /// let mut out = Vec::new();
+    /// let errors = builder.stream(&mut out).unwrap();
+    /// assert!(errors.is_empty());
+    /// assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", builder.build().unwrap()));
+    ///
+    /// let builder = DirTree::builder(&tmpdir).sort(SortOrder::Size).display(DisplayOptions {
+    ///     size: Some(SizeFormat::Bytes),
+    ///     du: true,
+    ///     ..DisplayOptions::default()
+    /// });
+    /// let mut out = Vec::new();
+    /// builder.stream(&mut out).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", builder.build().unwrap()));
+    ///
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    /// ```
+    pub fn stream<W: Write>(&self, out: &mut W) -> io::Result<Vec<ScanError>> {
+        if self.display.du
+            || self.sort == SortOrder::Size
+            || self.prune
+            || self.files_only
+            || self.hard_links
+            || self.hash
+        {
+            let (tree, errors) = self.build_tolerant()?;
+            writeln!(out, "{}", tree)?;
+            out.flush()?;
+            return Ok(errors);
+        }
+        let mut state = ReadState::new(true);
+        let root = self.read_entry(
+            self.root.clone(),
+            PathBuf::new(),
+            true,
+            &DirContext::root(self),
+            &mut state,
+        )?;
+        self.stream_entry(out, root, &mut Vec::new(), &mut state)?;
+        out.flush()?;
+        Ok(state.into_errors())
+    }
+
+    /// Writes the given entry, whose position is given by `lasts` as for
+    /// [VisitContext::lasts](crate::visit::VisitContext::lasts), and everything below it.
+    fn stream_entry<W: Write>(
+        &self,
+        out: &mut W,
+        entry: Entry,
+        lasts: &mut Vec<bool>,
+        state: &mut ReadState,
+    ) -> io::Result<()> {
+        let (mut node, context) = entry;
+        let mut children = match context {
+            Some(context) => {
+                out.flush()?;
+                self.read_contents(&mut node, context, state)?
+            }
+            None => Vec::new(),
+        };
+        if let Some((&last, ancestors)) = lasts.split_last() {
+            for &ancestor in ancestors {
+                out.write_all(if ancestor { BLANK } else { PIPE }.as_bytes())?;
+            }
+            out.write_all(if last { LAST_BRANCH } else { BRANCH }.as_bytes())?;
+        }
+        writeln!(out, "{}", node.value())?;
+
+        self.sort_children(&mut children, |(child, _)| child);
+        let count = children.len();
+        for (i, child) in children.into_iter().enumerate() {
+            lasts.push(i == count - 1);
+            let result = self.stream_entry(out, child, lasts, state);
+            lasts.pop();
+            result?;
+        }
+        Ok(())
+    }
+}
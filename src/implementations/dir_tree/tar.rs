@@ -0,0 +1,359 @@
+//! Listing the entries of tar archives, in the ustar, GNU and PAX formats.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::metadata::{S_IFBLK, S_IFCHR, S_IFDIR, S_IFIFO, S_IFLNK, S_IFREG};
+use super::paths::PathEntry;
+use super::{DirTree, DirTreeBuilder, EntryMetadata};
+
+/// The size of each header and of the blocks data is padded to.
+const BLOCK: usize = 512;
+
+/// The largest PAX or GNU long name header which is read, to guard against corrupt sizes.
+const MAX_EXTENDED: u64 = 1 << 20;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn unexpected_end() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "unexpected end of tar archive",
+    )
+}
+
+impl DirTreeBuilder {
+    /// Builds a tree from the entries of the tar archive read from `reader`, like
+    /// [Self::from_paths], without extracting it. If the archive is compressed with gzip, it is
+    /// decompressed with [GzDecoder](super::gzip::GzDecoder), unless the `gzip` feature is
+    /// disabled.
+    ///
+    /// See [entries] for what is read from the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if reading fails, or the archive is corrupt or cut short, in
+    /// which case the error describes the problem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::{DisplayOptions, SizeFormat};
+    ///
+    /// // Builds a header block for a tar archive.
+    /// let header = |name: &str, kind: u8, size: usize, mode: &str, link: &str| {
+    ///     let mut header = [0u8; 512];
+    ///     header[..name.len()].copy_from_slice(name.as_bytes());
+    ///     header[100..107].copy_from_slice(mode.as_bytes());
+    ///     header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+    ///     header[136..147].copy_from_slice(b"14737166720");
+    ///     header[148..156].fill(b' ');
+    ///     header[156] = kind;
+    ///     header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    ///     header[257..263].copy_from_slice(b"ustar\0");
+    ///     let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    ///     header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+    ///     header
+    /// };
+    /// let mut archive = Vec::new();
+    /// archive.extend(header("release/", b'5', 0, "0000755", ""));
+    /// archive.extend(header("release/bin/app", b'0', 600, "0000755", ""));
+    /// archive.extend([0; 1024]);
+    /// archive.extend(header("release/app", b'2', 0, "0000777", "bin/app"));
+    /// archive.extend([0; 1024]);
+    ///
+    /// let root = DirTree::builder("release.tar")
+    ///     .display(DisplayOptions {
+    ///         permissions: true,
+    ///         size: Some(SizeFormat::Bytes),
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .from_tar(&archive[..])
+    ///     .unwrap();
+    /// assert_eq!(format!("{}", root),
+    /// "[d---------           0]  release.tar
+    /// └── [drwxr-xr-x           0]  release
+    ///     ├── [lrwxrwxrwx           0]  app -> bin/app
+    ///     └── [d---------           0]  bin
+    ///         └── [-rwxr-xr-x         600]  app");
+    ///
+    /// assert!(DirTree::builder("cut.tar").from_tar(&archive[..700]).is_err());
+    /// ```
+    pub fn from_tar<R: Read>(&self, mut reader: R) -> io::Result<DirTree> {
+        // Read the first two bytes to tell whether the archive is compressed.
+        let mut magic = [0; 2];
+        let mut n = 0;
+        while n < magic.len() {
+            match reader.read(&mut magic[n..]) {
+                Ok(0) => break,
+                Ok(read) => n += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        let reader = (&magic[..n]).chain(reader);
+        let entries = match magic {
+            #[cfg(feature = "gzip")]
+            [0x1f, 0x8b] => entries(super::gzip::GzDecoder::new(reader))?,
+            #[cfg(not(feature = "gzip"))]
+            [0x1f, 0x8b] => return Err(invalid("gzip support is not enabled")),
+            _ => entries(reader)?,
+        };
+        Ok(self.clone().separator("/").from_paths(entries))
+    }
+}
+
+/// Returns the entries of the uncompressed tar archive read from `reader`, in the order they are
+/// archived, without extracting them.
+///
+/// Each entry has its size, and metadata with its mode, owner and group ids, and modification
+/// time, along with its target if it is a symbolic link. Archives in the ustar and GNU formats
+/// are understood, including long names and large numbers, as are PAX extended headers. Hard
+/// links are listed as empty regular files, along with the path of the entry they link to, and
+/// the contents of GNU sparse files are not expanded.
+///
+/// # Errors
+///
+/// This function will error if reading fails, a header's checksum is wrong, or the archive ends
+/// in the middle of an entry.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::implementations::dir_tree::tar::entries;
+///
+/// // Builds a header block for a tar archive.
+/// let header = |name: &str, kind: u8, size: usize, link: &str| {
+///     let mut header = [0u8; 512];
+///     header[..name.len()].copy_from_slice(name.as_bytes());
+///     header[100..107].copy_from_slice(b"0000644");
+///     header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+///     header[148..156].fill(b' ');
+///     header[156] = kind;
+///     header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+///     header[257..263].copy_from_slice(b"ustar\0");
+///     let sum: u32 = header.iter().map(|&b| b as u32).sum();
+///     header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+///     header
+/// };
+/// // Pads data to whole blocks.
+/// let padded = |data: &[u8]| {
+///     let mut data = data.to_vec();
+///     data.resize(data.len().div_ceil(512) * 512, 0);
+///     data
+/// };
+/// let long_name = format!("data/{}.bin", "x".repeat(120));
+/// let mut archive = Vec::new();
+/// // A PAX header with the size of the next entry, then a GNU header with its long name.
+/// archive.extend(header("PaxHeader", b'x', 12, ""));
+/// archive.extend(padded(b"12 size=700\n"));
+/// archive.extend(header("././@LongLink", b'L', long_name.len() + 1, ""));
+/// archive.extend(padded(format!("{}\0", long_name).as_bytes()));
+/// archive.extend(header("data/cut", b'0', 0, ""));
+/// archive.extend(padded(&[1; 700]));
+/// archive.extend(header("data/small", b'0', 10, ""));
+/// archive.extend(padded(&[2; 10]));
+/// archive.extend(header("data/copy", b'1', 0, "data/small"));
+/// archive.extend([0; 1024]);
+///
+/// let entries = entries(&archive[..]).unwrap();
+/// let listed: Vec<_> = (entries.iter())
+///     .map(|e| (e.path.as_str(), e.size, e.hard_link.as_deref()))
+///     .collect();
+/// assert_eq!(listed, [
+///     (long_name.as_str(), 700, None),
+///     ("data/small", 10, None),
+///     ("data/copy", 0, Some("data/small")),
+/// ]);
+/// ```
+pub fn entries<R: Read>(mut reader: R) -> io::Result<Vec<PathEntry>> {
+    let mut entries = Vec::new();
+    // The PAX records which apply to every following entry, or only to the next one.
+    let mut global: HashMap<String, String> = HashMap::new();
+    let mut local: HashMap<String, String> = HashMap::new();
+    let mut header = [0; BLOCK];
+    while read_block(&mut reader, &mut header)? {
+        // The archive ends with blocks of zeros.
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let expected = number(&header[148..156])?;
+        let sum: u64 = (header.iter().enumerate())
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+            .sum();
+        if sum != expected {
+            return Err(invalid("invalid tar header checksum"));
+        }
+        let kind = header[156];
+        match kind {
+            b'x' | b'g' | b'L' | b'K' => {
+                let data = read_extended(&mut reader, number(&header[124..136])?)?;
+                match kind {
+                    b'x' => local.extend(pax_records(&data)?),
+                    b'g' => global.extend(pax_records(&data)?),
+                    // GNU long names are stored as entries of their own, before the entry.
+                    b'L' => drop(local.insert(String::from("path"), text(&data))),
+                    _ => drop(local.insert(String::from("linkpath"), text(&data))),
+                }
+                continue;
+            }
+            _ => (),
+        }
+        // A PAX size only applies to the entry after it, and is needed for sizes too large for
+        // the header.
+        let size = match local.get("size") {
+            Some(size) => parse_decimal(size)?,
+            None => number(&header[124..136])?,
+        };
+        let record = |key: &str| local.get(key).or_else(|| global.get(key)).cloned();
+        let path = record("path").unwrap_or_else(|| {
+            let name = text(&header[..100]);
+            let prefix = text(&header[345..500]);
+            // Only ustar headers have a prefix, and GNU headers use the space for other fields.
+            match &header[257..263] == b"ustar\0" && !prefix.is_empty() {
+                true => format!("{}/{}", prefix, name),
+                false => name,
+            }
+        });
+        let file_type = match kind {
+            b'2' => S_IFLNK,
+            b'3' => S_IFCHR,
+            b'4' => S_IFBLK,
+            b'5' => S_IFDIR,
+            b'6' => S_IFIFO,
+            _ => S_IFREG,
+        };
+        let id = |key: &str, field: &[u8]| match record(key) {
+            Some(value) => parse_decimal(&value),
+            None => number(field),
+        };
+        let modified = match record("mtime") {
+            // PAX times may have a fraction of a second, which is not shown anyway.
+            Some(time) => parse_decimal(time.split('.').next().unwrap_or_default())?,
+            None => number(&header[136..148])?,
+        };
+        let metadata = EntryMetadata {
+            mode: number(&header[100..108])? as u32 & 0o7777 | file_type,
+            uid: id("uid", &header[108..116])? as u32,
+            gid: id("gid", &header[116..124])? as u32,
+            links: 1,
+            modified: UNIX_EPOCH.checked_add(Duration::from_secs(modified)),
+            ..EntryMetadata::default()
+        };
+        let link = || record("linkpath").unwrap_or_else(|| text(&header[157..257]));
+        entries.push(PathEntry {
+            path,
+            size: if file_type == S_IFREG { size } else { 0 },
+            metadata: Some(metadata),
+            target: (kind == b'2').then(link),
+            compressed_size: None,
+            hard_link: (kind == b'1').then(link),
+        });
+        local.clear();
+        skip(&mut reader, size)?;
+    }
+    Ok(entries)
+}
+
+/// Fills the given block, returning `false` if the archive ended before it.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+    let mut n = 0;
+    while n < BLOCK {
+        match reader.read(&mut block[n..]) {
+            Ok(0) if n == 0 => return Ok(false),
+            Ok(0) => return Err(unexpected_end()),
+            Ok(read) => n += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Returns the padded size of data of the given size.
+fn padded(size: u64) -> u64 {
+    size.div_ceil(BLOCK as u64) * BLOCK as u64
+}
+
+/// Skips data of the given size, along with its padding.
+fn skip<R: Read>(reader: &mut R, size: u64) -> io::Result<()> {
+    let padded = padded(size);
+    if io::copy(&mut reader.take(padded), &mut io::sink())? < padded {
+        return Err(unexpected_end());
+    }
+    Ok(())
+}
+
+/// Reads the data of an extended header of the given size, along with its padding.
+fn read_extended<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    if size > MAX_EXTENDED {
+        return Err(invalid("tar extended header is too large"));
+    }
+    let mut data = vec![0; padded(size) as usize];
+    reader.read_exact(&mut data).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => unexpected_end(),
+        _ => e,
+    })?;
+    data.truncate(size as usize);
+    Ok(data)
+}
+
+/// Returns the text in the given field, which ends at the first zero byte, if any.
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Returns the number in the given numeric field, which is either octal text, or a big-endian
+/// binary number if the high bit of its first byte is set, as written by GNU tar for numbers
+/// too large for octal.
+fn number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut value: u64 = (field[0] & 0x7f) as u64;
+        for &byte in &field[1..] {
+            value = value
+                .checked_mul(256)
+                .ok_or_else(|| invalid("tar header number is too large"))?
+                | byte as u64;
+        }
+        return Ok(value);
+    }
+    let digits = text(field);
+    let digits = digits.trim_matches(|c| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("invalid number in tar header"))
+}
+
+fn parse_decimal(value: &str) -> io::Result<u64> {
+    value
+        .parse()
+        .map_err(|_| invalid("invalid number in pax header"))
+}
+
+/// Returns the records in the data of a PAX extended header, each written as
+/// `<length> <key>=<value>\n`, where the length includes the whole record.
+fn pax_records(mut data: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let mut records = Vec::new();
+    while !data.is_empty() && data[0] != 0 {
+        let space =
+            (data.iter().position(|&b| b == b' ')).ok_or_else(|| invalid("invalid pax header"))?;
+        let length: usize = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|length| length.parse().ok())
+            .filter(|&length| length > space && length <= data.len())
+            .ok_or_else(|| invalid("invalid pax header"))?;
+        let record = String::from_utf8_lossy(&data[space + 1..length]);
+        let (key, value) = (record.strip_suffix('\n'))
+            .and_then(|record| record.split_once('='))
+            .ok_or_else(|| invalid("invalid pax header"))?;
+        records.push((key.to_string(), value.to_string()));
+        data = &data[length..];
+    }
+    Ok(records)
+}
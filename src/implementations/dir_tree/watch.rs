@@ -0,0 +1,565 @@
+//! Reading a [DirTree] again whenever the filesystem changes, for a live view of a directory.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::{DirTree, DirTreeBuilder, ScanError};
+use crate::node::Node;
+
+/// How an entry differs from the previous frame of a [Watcher].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The entry was not in the previous frame.
+    Added,
+    /// The entry is not a directory, and its type, size or modification time changed, or there is
+    /// a new error reading it.
+    Modified,
+}
+
+/// [Frame] is one reading of the tree by a [Watcher], along with how it differs from the
+/// previous one.
+#[derive(Debug)]
+pub struct Frame {
+    /// The tree read for this frame.
+    pub tree: DirTree,
+    /// The errors encountered while reading the tree, as for
+    /// [DirTreeBuilder::build_tolerant].
+    pub errors: Vec<ScanError>,
+    changes: HashMap<PathBuf, Change>,
+    removed: Vec<PathBuf>,
+}
+
+impl Frame {
+    /// Returns how the entry with the given path differs from the previous frame, if at all.
+    /// Nothing has changed in the first frame.
+    pub fn change(&self, path: &Path) -> Option<Change> {
+        self.changes.get(path).copied()
+    }
+
+    /// Returns the paths of the entries in the previous frame which are no longer in the tree,
+    /// in the order they were displayed.
+    pub fn removed(&self) -> &[PathBuf] {
+        &self.removed
+    }
+
+    /// Returns the tree as it is displayed, where if `highlight` is set, the names of added
+    /// entries are shown in green and those of modified entries in yellow, using ANSI escape
+    /// codes. Without highlighting, this is the same as displaying [Frame::tree].
+    pub fn render(&self, highlight: bool) -> String {
+        Highlighted::new(self, &self.tree, highlight).to_string()
+    }
+}
+
+/// A node of the tree of a [Frame] as it is rendered, with its value highlighted if it changed.
+struct Highlighted<'a> {
+    frame: &'a Frame,
+    node: &'a DirTree,
+    highlight: bool,
+    children: Vec<Highlighted<'a>>,
+}
+
+impl<'a> Highlighted<'a> {
+    fn new(frame: &'a Frame, node: &'a DirTree, highlight: bool) -> Self {
+        Highlighted {
+            frame,
+            node,
+            highlight,
+            children: (node.children.iter())
+                .map(|child| Highlighted::new(frame, child, highlight))
+                .collect(),
+        }
+    }
+}
+
+impl Node for Highlighted<'_> {
+    fn value(&self) -> impl fmt::Display {
+        let color = match self.frame.change(&self.node.path) {
+            Some(Change::Added) if self.highlight => Some("32"),
+            Some(Change::Modified) if self.highlight => Some("33"),
+            _ => None,
+        };
+        match color {
+            Some(color) => format!("\x1b[1;{}m{}\x1b[0m", color, self.node.value()),
+            None => self.node.value().to_string(),
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter()
+    }
+}
+
+impl fmt::Display for Highlighted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Node::fmt(self, f)
+    }
+}
+
+/// What is compared between frames to find modified entries.
+type Fingerprint = (u32, u64, Option<SystemTime>, Option<&'static str>);
+
+fn fingerprint(node: &DirTree) -> Fingerprint {
+    let metadata = &node.metadata;
+    (metadata.mode, node.size, metadata.modified, node.error)
+}
+
+/// Adds the fingerprint of every entry of the given tree to `entries`, in display order.
+fn collect(node: &DirTree, entries: &mut Vec<(PathBuf, bool, Fingerprint)>) {
+    entries.push((node.path.clone(), node.is_dir, fingerprint(node)));
+    node.children.iter().for_each(|c| collect(c, entries));
+}
+
+/// Returns a hash of the fingerprint of every entry of the given tree, which changes whenever
+/// anything displayed in the tree changes.
+fn tree_hash(node: &DirTree) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut entries = Vec::new();
+    collect(node, &mut entries);
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How a [Watcher] waits for the filesystem to change.
+#[derive(Debug)]
+enum Notifier {
+    /// Linux inotify, watching every directory of the last frame.
+    #[cfg(target_os = "linux")]
+    Inotify(inotify::Inotify),
+    /// Read the tree again after each interval, and compare it to the last time it was read,
+    /// which had the given hash.
+    Poll { interval: Duration, hash: u64 },
+}
+
+impl Notifier {
+    /// Returns the notifier used once inotify cannot watch every directory of the tree.
+    fn fallback() -> Self {
+        Notifier::Poll {
+            interval: Duration::from_secs(1),
+            hash: 0,
+        }
+    }
+}
+
+/// [Watcher] reads a [DirTree] again each time the filesystem below its root changes.
+///
+/// On Linux, changes are detected with inotify, which watches every directory in the tree, and
+/// each directory created in it as soon as it appears. If inotify is unavailable, such as when
+/// the limit on the number of watches is reached, or on other platforms, the tree is read again
+/// at a regular interval instead, and compared with the last frame.
+///
+/// Changes usually come in bursts, such as while a build writes its output, so a new frame is
+/// only read once the filesystem has been quiet for the debounce time.
+///
+/// # Examples
+///
+/// ```
+/// use std::env;
+/// use std::fs;
+/// use std::time::Duration;
+/// use simple_tree::implementations::DirTree;
+/// use simple_tree::implementations::dir_tree::watch::{Change, Watcher};
+///
+/// let tmpdir = env::temp_dir().join("test-dir-tree-watcher");
+/// fs::create_dir_all(tmpdir.join("out")).unwrap();
+/// fs::write(tmpdir.join("out/app"), "v1").unwrap();
+///
+/// let builder = DirTree::builder(&tmpdir);
+/// let mut watcher = Watcher::new(&builder).debounce(Duration::from_millis(20));
+/// let first = watcher.next_frame().unwrap();
+/// assert_eq!(first.render(true), format!("{}", first.tree));
+///
+/// fs::write(tmpdir.join("out/app"), "version 2").unwrap();
+/// fs::write(tmpdir.join("out/app.map"), "").unwrap();
+/// let second = watcher.next_frame().unwrap();
+/// fs::remove_dir_all(&tmpdir).unwrap();
+///
+/// assert_eq!(second.change(&tmpdir.join("out/app")), Some(Change::Modified));
+/// assert_eq!(second.change(&tmpdir.join("out/app.map")), Some(Change::Added));
+/// assert_eq!(second.render(true), format!(
+/// "{}
+/// └── out
+///     ├── \x1b[1;33mapp\x1b[0m
+///     └── \x1b[1;32mapp.map\x1b[0m", tmpdir.display()));
+/// ```
+#[derive(Debug)]
+pub struct Watcher<'a> {
+    builder: &'a DirTreeBuilder,
+    notifier: Notifier,
+    debounce: Duration,
+    // The fingerprint of every entry of the last frame, in display order.
+    previous: Option<Vec<(PathBuf, bool, Fingerprint)>>,
+}
+
+impl<'a> Watcher<'a> {
+    /// Returns a watcher which reads trees with the given builder, using inotify if possible.
+    pub fn new(builder: &'a DirTreeBuilder) -> Self {
+        #[cfg(target_os = "linux")]
+        if let Ok(inotify) = inotify::Inotify::new() {
+            return Self::with_notifier(builder, Notifier::Inotify(inotify));
+        }
+        Self::polling(builder, Duration::from_secs(1))
+    }
+
+    /// Returns a watcher which reads trees with the given builder, and which checks for changes
+    /// by reading the tree again after each interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use std::time::Duration;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::watch::{Change, Watcher};
+    ///
+    /// let tmpdir = env::temp_dir().join("test-dir-tree-watcher-polling");
+    /// fs::create_dir_all(tmpdir.join("out")).unwrap();
+    /// fs::write(tmpdir.join("out/old"), "").unwrap();
+    ///
+    /// let builder = DirTree::builder(&tmpdir);
+    /// let mut watcher = Watcher::polling(&builder, Duration::from_millis(10))
+    ///     .debounce(Duration::from_millis(10));
+    /// assert!(watcher.is_polling());
+    /// watcher.next_frame().unwrap();
+    ///
+    /// fs::remove_file(tmpdir.join("out/old")).unwrap();
+    /// fs::create_dir(tmpdir.join("out/new")).unwrap();
+    /// let frame = watcher.next_frame().unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// assert_eq!(frame.change(&tmpdir.join("out/new")), Some(Change::Added));
+    /// assert_eq!(frame.change(&tmpdir.join("out")), None);
+    /// assert_eq!(frame.removed(), [tmpdir.join("out/old")]);
+    /// ```
+    pub fn polling(builder: &'a DirTreeBuilder, interval: Duration) -> Self {
+        Self::with_notifier(builder, Notifier::Poll { interval, hash: 0 })
+    }
+
+    fn with_notifier(builder: &'a DirTreeBuilder, notifier: Notifier) -> Self {
+        Watcher {
+            builder,
+            notifier,
+            debounce: Duration::from_millis(200),
+            previous: None,
+        }
+    }
+
+    /// Sets how long the filesystem must go without changing before a new frame is read. The
+    /// default is 200 milliseconds.
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns whether changes are detected by reading the tree at a regular interval, rather
+    /// than being notified of them.
+    pub fn is_polling(&self) -> bool {
+        matches!(self.notifier, Notifier::Poll { .. })
+    }
+
+    /// Reads the tree, returning it as a [Frame]. The first frame is read straight away, while
+    /// each later frame is read once the filesystem has changed since the last, and has then
+    /// been quiet for the debounce time.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the root of the tree cannot be read, or if waiting for
+    /// changes fails.
+    pub fn next_frame(&mut self) -> io::Result<Frame> {
+        if self.previous.is_some() {
+            self.wait()?;
+        }
+        let (tree, errors) = self.builder.build_tolerant()?;
+
+        let mut entries = Vec::new();
+        collect(&tree, &mut entries);
+        let mut changes = HashMap::new();
+        let mut removed = Vec::new();
+        if let Some(previous) = &self.previous {
+            let before: HashMap<_, _> = previous
+                .iter()
+                .map(|(path, is_dir, fp)| (path, (*is_dir, *fp)))
+                .collect();
+            for (path, is_dir, fp) in &entries {
+                match before.get(path) {
+                    None => {
+                        changes.insert(path.clone(), Change::Added);
+                    }
+                    Some((was_dir, old)) if !(*is_dir && *was_dir) && old != fp => {
+                        changes.insert(path.clone(), Change::Modified);
+                    }
+                    Some(_) => (),
+                }
+            }
+            let now: HashSet<_> = entries.iter().map(|(path, _, _)| path).collect();
+            removed = previous
+                .iter()
+                .map(|(path, _, _)| path)
+                .filter(|path| !now.contains(path))
+                .cloned()
+                .collect();
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Notifier::Inotify(inotify) = &mut self.notifier
+            && inotify.watch_tree(&tree).is_err()
+        {
+            self.notifier = Notifier::fallback();
+        }
+        if let Notifier::Poll { hash, .. } = &mut self.notifier {
+            *hash = tree_hash(&tree);
+        }
+        self.previous = Some(entries);
+        Ok(Frame {
+            tree,
+            errors,
+            changes,
+            removed,
+        })
+    }
+
+    /// Waits until the filesystem has changed since the last frame, and has then been quiet for
+    /// the debounce time.
+    fn wait(&mut self) -> io::Result<()> {
+        match &mut self.notifier {
+            #[cfg(target_os = "linux")]
+            Notifier::Inotify(inotify) => {
+                let mut created = Vec::new();
+                while !inotify.wait(None, &mut created)? {}
+                loop {
+                    // Directories created since the last frame are watched as soon as they
+                    // appear, so that changes inside them are seen.
+                    if created
+                        .drain(..)
+                        .try_for_each(|path| inotify.watch_new(&path))
+                        .is_err()
+                    {
+                        // Changes inside the new directories may be missed, so the tree is read
+                        // straight away, and polled from then on.
+                        self.notifier = Notifier::fallback();
+                        return Ok(());
+                    }
+                    if !inotify.wait(Some(self.debounce), &mut created)? {
+                        return Ok(());
+                    }
+                }
+            }
+            Notifier::Poll { interval, hash } => {
+                loop {
+                    thread::sleep(*interval);
+                    if changed(self.builder, hash)? {
+                        break;
+                    }
+                }
+                // Keep reading until the tree stops changing.
+                loop {
+                    thread::sleep(self.debounce);
+                    if !changed(self.builder, hash)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reads the tree again with the given builder, returning whether it differs from the last time
+/// it was read, which had the given hash, and updating the hash.
+fn changed(builder: &DirTreeBuilder, hash: &mut u64) -> io::Result<bool> {
+    let (tree, _) = builder.build_tolerant()?;
+    let previous = std::mem::replace(hash, tree_hash(&tree));
+    Ok(*hash != previous)
+}
+
+/// Linux inotify, used through the C library which the standard library already links. Its
+/// wrappers are called rather than making the system calls directly, since the numbers of the
+/// system calls differ between architectures, and some, such as aarch64, have no `poll` at all.
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::collections::HashMap;
+    use std::ffi::{CString, OsStr, c_char, c_int, c_ulong};
+    use std::fs::{self, File};
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use super::DirTree;
+
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const IN_MODIFY: u32 = 0x002;
+    const IN_ATTRIB: u32 = 0x004;
+    const IN_CLOSE_WRITE: u32 = 0x008;
+    const IN_MOVED_FROM: u32 = 0x040;
+    const IN_MOVED_TO: u32 = 0x080;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+    const IN_DELETE_SELF: u32 = 0x400;
+    const IN_MOVE_SELF: u32 = 0x800;
+    const IN_IGNORED: u32 = 0x8000;
+    const IN_ISDIR: u32 = 0x40000000;
+    const MASK: u32 = IN_MODIFY
+        | IN_ATTRIB
+        | IN_CLOSE_WRITE
+        | IN_MOVED_FROM
+        | IN_MOVED_TO
+        | IN_CREATE
+        | IN_DELETE
+        | IN_DELETE_SELF
+        | IN_MOVE_SELF;
+    /// The size of an event without its name.
+    const EVENT_SIZE: usize = 16;
+    const POLLIN: i16 = 0x001;
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: i16,
+        revents: i16,
+    }
+
+    unsafe extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+    }
+
+    /// An inotify instance, which is closed when dropped.
+    #[derive(Debug)]
+    pub(super) struct Inotify {
+        file: File,
+        // The directory of each watch.
+        watches: HashMap<c_int, PathBuf>,
+    }
+
+    impl Inotify {
+        pub(super) fn new() -> io::Result<Self> {
+            // SAFETY: `inotify_init1` takes no pointers.
+            let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the descriptor is open, and nothing else owns it.
+            let file = unsafe { File::from_raw_fd(fd) };
+            Ok(Inotify {
+                file,
+                watches: HashMap::new(),
+            })
+        }
+
+        /// Watches every directory in the given tree whose contents were read. Watching a
+        /// directory again has no effect, and watches of removed directories are removed by the
+        /// kernel.
+        pub(super) fn watch_tree(&mut self, node: &DirTree) -> io::Result<()> {
+            if !node.is_dir || node.truncated {
+                return Ok(());
+            }
+            self.watch(&node.path)?;
+            node.children.iter().try_for_each(|c| self.watch_tree(c))
+        }
+
+        /// Watches the given directory and every directory below it, which were created after
+        /// the last frame was read, and so may not be watched yet.
+        pub(super) fn watch_new(&mut self, path: &Path) -> io::Result<()> {
+            self.watch(path)?;
+            // The directory may already have been removed, or may be unreadable, in which case
+            // there is nothing below it to watch.
+            let Ok(entries) = fs::read_dir(path) else {
+                return Ok(());
+            };
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    self.watch_new(&entry.path())?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Watches the given directory, unless it has been removed.
+        fn watch(&mut self, path: &Path) -> io::Result<()> {
+            let c_path = CString::new(path.as_os_str().as_bytes())?;
+            // SAFETY: the path is a valid nul-terminated string for the duration of the call.
+            let wd = unsafe { inotify_add_watch(self.file.as_raw_fd(), c_path.as_ptr(), MASK) };
+            if wd < 0 {
+                let error = io::Error::last_os_error();
+                // The directory may have been removed since it was read.
+                return match error.kind() {
+                    io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(error),
+                };
+            }
+            self.watches.insert(wd, path.to_path_buf());
+            Ok(())
+        }
+
+        /// Waits for events for up to the given time, or for ever if no time is given, and
+        /// discards them, returning whether there were any. The paths of directories which were
+        /// created are added to `created`, so that they can be watched.
+        pub(super) fn wait(
+            &mut self,
+            timeout: Option<Duration>,
+            created: &mut Vec<PathBuf>,
+        ) -> io::Result<bool> {
+            let timeout = timeout.map_or(-1, |t| t.as_millis().min(c_int::MAX as u128) as c_int);
+            let mut fds = PollFd {
+                fd: self.file.as_raw_fd(),
+                events: POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `fds` points to one valid `PollFd` for the duration of the call.
+            let ready = unsafe { poll(&mut fds, 1, timeout) };
+            if ready < 0 {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::Interrupted => Ok(false),
+                    _ => Err(error),
+                };
+            }
+            if ready == 0 {
+                return Ok(false);
+            }
+            let mut buf = [0; 4096];
+            loop {
+                match self.file.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => self.read_events(&buf[..n], created),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(true)
+        }
+
+        /// Reads the given events, adding the paths of directories which were created or moved
+        /// into a watched directory to `created`, and forgetting watches which were removed.
+        fn read_events(&mut self, mut events: &[u8], created: &mut Vec<PathBuf>) {
+            while events.len() >= EVENT_SIZE {
+                let field = |i: usize| u32::from_ne_bytes([0, 1, 2, 3].map(|j| events[i + j]));
+                let (wd, mask, len) = (field(0) as c_int, field(4), field(12) as usize);
+                let Some(name) = events.get(EVENT_SIZE..EVENT_SIZE + len) else {
+                    return;
+                };
+                // The name is padded with zero bytes.
+                let end = name.iter().position(|&b| b == 0).unwrap_or(len);
+                let name = OsStr::from_bytes(&name[..end]);
+                if mask & IN_IGNORED != 0 {
+                    self.watches.remove(&wd);
+                } else if mask & IN_ISDIR != 0
+                    && mask & (IN_CREATE | IN_MOVED_TO) != 0
+                    && let Some(dir) = self.watches.get(&wd)
+                {
+                    created.push(dir.join(name));
+                }
+                events = &events[EVENT_SIZE + len..];
+            }
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Listing the entries of zip archives, such as `.zip`, `.jar` and `.whl` files, from their
+//! central directory.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::metadata::{self, S_IFDIR, S_IFLNK, S_IFMT, S_IFREG};
+use super::paths::PathEntry;
+use super::{DirTree, DirTreeBuilder, EntryMetadata};
+
+/// The signatures which start each record.
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END: u32 = 0x06054b50;
+const ZIP64_END: u32 = 0x06064b50;
+const ZIP64_LOCATOR: u32 = 0x07064b50;
+
+/// The sizes of the fixed parts of each record.
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const END_SIZE: usize = 22;
+const ZIP64_END_SIZE: usize = 56;
+const ZIP64_LOCATOR_SIZE: usize = 20;
+
+/// The ids of the extra fields which are read.
+const ZIP64_EXTRA: u16 = 0x0001;
+const TIMESTAMP_EXTRA: u16 = 0x5455;
+const UNIX_IDS_EXTRA: u16 = 0x7875;
+
+/// The system which wrote an entry, from the high byte of its version, when its external
+/// attributes hold a unix mode.
+const UNIX_HOST: u8 = 3;
+
+/// The longest symbolic link target which is read.
+const MAX_TARGET: u64 = 4096;
+
+/// The characters of code page 437 from `0x80` up, which names are written in unless they are
+/// marked as UTF-8.
+const CP437: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦\
+╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap())
+}
+
+impl DirTreeBuilder {
+    /// Builds a tree from the entries of the zip archive read from `reader`, like
+    /// [Self::from_paths], such as a `.zip`, `.jar` or `.whl` file. Only the central directory
+    /// at the end of the archive is read, so nothing is decompressed.
+    ///
+    /// Each entry has its compressed size along with its size, which is shown if
+    /// [DisplayOptions::compressed](super::DisplayOptions::compressed) is set. See [entries] for
+    /// what else is read from the archive.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if reading fails, or the archive is not a zip archive or is
+    /// corrupt, in which case the error describes the problem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::implementations::dir_tree::{DisplayOptions, SizeFormat};
+    ///
+    /// // Builds a central directory entry for a zip archive written on unix.
+    /// let entry = |name: &str, mode: u32, compressed: u32, size: u32| {
+    ///     let mut entry = b"PK\x01\x02\x1e\x03\x14\x00\x00\x00\x08\x00".to_vec();
+    ///     entry.extend(0x5b21u16.to_le_bytes()); // 11:25:02
+    ///     entry.extend(0x58cdu16.to_le_bytes()); // 2024-06-13
+    ///     entry.extend([0; 4]);
+    ///     entry.extend(compressed.to_le_bytes());
+    ///     entry.extend(size.to_le_bytes());
+    ///     entry.extend((name.len() as u16).to_le_bytes());
+    ///     entry.extend([0; 8]);
+    ///     entry.extend((mode << 16).to_le_bytes());
+    ///     entry.extend([0; 4]);
+    ///     entry.extend(name.as_bytes());
+    ///     entry
+    /// };
+    /// let mut directory = entry("app/", 0o040755, 0, 0);
+    /// directory.extend(entry("app/main.py", 0o100644, 1100, 4096));
+    /// directory.extend(entry("app/data.bin", 0o100644, 5000, 5000));
+    /// let mut archive = directory.clone();
+    /// archive.extend(b"PK\x05\x06\0\0\0\0\x03\0\x03\0");
+    /// archive.extend((directory.len() as u32).to_le_bytes());
+    /// archive.extend([0; 6]);
+    ///
+    /// let root = DirTree::builder("app.whl")
+    ///     .display(DisplayOptions {
+    ///         size: Some(SizeFormat::Iec),
+    ///         compressed: true,
+    ///         modified: Some(String::from("%Y-%m-%d")),
+    ///         ..DisplayOptions::default()
+    ///     })
+    ///     .from_zip(Cursor::new(&archive))
+    ///     .unwrap();
+    /// assert_eq!(format!("{}", root),
+    /// "[   0      ?]  app.whl
+    /// └── [   0    0 2024-06-13]  app
+    ///     ├── [4.9K 4.9K 2024-06-13]  data.bin
+    ///     └── [4.0K 1.1K 2024-06-13]  main.py");
+    ///
+    /// let error = DirTree::builder("app.whl").from_zip(Cursor::new(&directory)).unwrap_err();
+    /// assert_eq!(error.to_string(), "not a zip archive: no end of central directory record");
+    /// ```
+    pub fn from_zip<R: Read + Seek>(&self, reader: R) -> io::Result<DirTree> {
+        Ok(self.clone().separator("/").from_paths(entries(reader)?))
+    }
+}
+
+/// Returns the entries of the zip archive read from `reader`, in the order they are listed in its
+/// central directory, without decompressing anything.
+///
+/// Each entry has its size and compressed size, and metadata with its modification time and, if
+/// the archive was written on unix, its mode. Times are read from the extended timestamp field if
+/// there is one, and otherwise from the DOS time of the entry, which has no time zone and is
+/// taken to be in UTC. The owner and group ids are read from the Info-ZIP unix field if there is
+/// one. The targets of symbolic links are read from the archive if they are not compressed.
+///
+/// Archives in the Zip64 format are understood, as are archives with other data before them, such
+/// as self-extracting archives. Names are read as UTF-8 if they are marked as such or are valid
+/// UTF-8, and in code page 437 otherwise.
+///
+/// # Errors
+///
+/// This function will error if reading fails, the archive has no end of central directory record,
+/// is split across several disks, or its central directory is corrupt or cut short.
+pub fn entries<R: Read + Seek>(mut reader: R) -> io::Result<Vec<PathEntry>> {
+    let directory = find_directory(&mut reader)?;
+    let mut bytes = vec![0; directory.size as usize];
+    reader.seek(SeekFrom::Start(directory.base + directory.offset))?;
+    reader.read_exact(&mut bytes).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => invalid("zip central directory is cut short"),
+        _ => e,
+    })?;
+    let mut entries = Vec::new();
+    let mut rest = &bytes[..];
+    for i in 1..=directory.count {
+        let error =
+            |problem: &str| invalid(&format!("zip central directory entry {}: {}", i, problem));
+        if rest.len() < CENTRAL_HEADER_SIZE {
+            return Err(error("cut short"));
+        }
+        if u32_at(rest, 0) != CENTRAL_HEADER {
+            return Err(error("invalid signature"));
+        }
+        let name_len = u16_at(rest, 28) as usize;
+        let extra_len = u16_at(rest, 30) as usize;
+        let comment_len = u16_at(rest, 32) as usize;
+        let end = CENTRAL_HEADER_SIZE + name_len + extra_len + comment_len;
+        if rest.len() < end {
+            return Err(error("cut short"));
+        }
+        let header = &rest[..CENTRAL_HEADER_SIZE];
+        let (name, extra) = rest[CENTRAL_HEADER_SIZE..].split_at(name_len);
+        let extra = &extra[..extra_len];
+        rest = &rest[end..];
+
+        // Each size and offset which is too large for its field is in the Zip64 extra field.
+        let mut size = u32_at(header, 24) as u64;
+        let mut compressed_size = u32_at(header, 20) as u64;
+        let mut offset = u32_at(header, 42) as u64;
+        let mut modified = dos_time(u16_at(header, 14), u16_at(header, 12));
+        let (mut uid, mut gid) = (0, 0);
+        for (id, data) in extra_fields(extra) {
+            match id {
+                ZIP64_EXTRA => {
+                    let mut values = data.chunks_exact(8).map(|v| u64_at(v, 0));
+                    for field in [&mut size, &mut compressed_size, &mut offset] {
+                        if *field == u32::MAX as u64 {
+                            *field = values.next().ok_or_else(|| error("invalid zip64 field"))?;
+                        }
+                    }
+                }
+                // The modification time is first, if the first flag is set.
+                TIMESTAMP_EXTRA if data.len() >= 5 && data[0] & 1 != 0 => {
+                    let secs = i32::from_le_bytes(data[1..5].try_into().unwrap());
+                    modified = Some(match secs >= 0 {
+                        true => UNIX_EPOCH + Duration::from_secs(secs as u64),
+                        false => UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs() as u64),
+                    });
+                }
+                // A version, then the size and value of the uid, then those of the gid.
+                UNIX_IDS_EXTRA if data.len() >= 2 && data[0] == 1 => {
+                    let id = |data: &[u8]| -> Option<(u32, usize)> {
+                        let len = *data.first()? as usize;
+                        let value = data.get(1..1 + len)?.iter().rev();
+                        let value = value.fold(0u64, |value, &b| value << 8 | b as u64);
+                        Some((value as u32, 1 + len))
+                    };
+                    if let Some((u, len)) = id(&data[1..])
+                        && let Some((g, _)) = id(&data[1 + len..])
+                    {
+                        (uid, gid) = (u, g);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let flags = u16_at(header, 8);
+        let path = match (flags & 0x800 != 0, std::str::from_utf8(name)) {
+            (_, Ok(name)) => name.to_string(),
+            (true, Err(_)) => String::from_utf8_lossy(name).into_owned(),
+            (false, Err(_)) => name.iter().map(|&b| cp437(b)).collect(),
+        };
+        let is_dir = path.ends_with('/');
+        let external = u32_at(header, 38);
+        let mut mode = match header[5] {
+            UNIX_HOST => external >> 16,
+            _ => 0,
+        };
+        if mode & S_IFMT == 0 {
+            // Otherwise, the attributes are those of MS-DOS, where 0x10 is a directory and 0x01
+            // is read-only.
+            let is_dir = is_dir || external & 0x10 != 0;
+            let read_only = external & 0x01 != 0;
+            mode |= match (is_dir, read_only) {
+                (true, false) => S_IFDIR | 0o755,
+                (true, true) => S_IFDIR | 0o555,
+                (false, false) => S_IFREG | 0o644,
+                (false, true) => S_IFREG | 0o444,
+            };
+        }
+        // Symbolic links written on unix store their targets as their contents.
+        let method = u16_at(header, 10);
+        let target = match mode & S_IFMT == S_IFLNK && method == 0 && size <= MAX_TARGET {
+            true => Some(read_target(&mut reader, directory.base + offset, size, i)?),
+            false => None,
+        };
+        let is_file = mode & S_IFMT == S_IFREG;
+        entries.push(PathEntry {
+            path,
+            size: if is_file { size } else { 0 },
+            metadata: Some(EntryMetadata {
+                mode,
+                uid,
+                gid,
+                links: 1,
+                modified,
+                ..EntryMetadata::default()
+            }),
+            target,
+            compressed_size: Some(if is_file { compressed_size } else { 0 }),
+            hard_link: None,
+        });
+    }
+    Ok(entries)
+}
+
+/// Where the central directory of an archive is, and how many entries it has.
+struct Directory {
+    // The number of bytes before the archive, such as the program of a self-extracting archive,
+    // which the offsets in the archive do not count.
+    base: u64,
+    offset: u64,
+    size: u64,
+    count: u64,
+}
+
+/// Finds the central directory from the end of central directory record, and the Zip64 record
+/// before it, if there is one.
+fn find_directory<R: Read + Seek>(reader: &mut R) -> io::Result<Directory> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    // The record is at the end, followed by a comment of up to 65535 bytes.
+    let tail_len = len.min((END_SIZE + u16::MAX as usize) as u64);
+    let mut tail = vec![0; tail_len as usize];
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    reader.read_exact(&mut tail)?;
+    let start = (0..tail.len().saturating_sub(END_SIZE - 1))
+        .rev()
+        .find(|&i| {
+            u32_at(&tail, i) == END && i + END_SIZE + u16_at(&tail, i + 20) as usize <= tail.len()
+        })
+        .ok_or_else(|| invalid("not a zip archive: no end of central directory record"))?;
+    let end = &tail[start..start + END_SIZE];
+    let end_position = len - tail_len + start as u64;
+    if u16_at(end, 4) != 0 || u16_at(end, 6) != 0 {
+        return Err(invalid(
+            "zip archives split across several disks are not supported",
+        ));
+    }
+    let mut directory = Directory {
+        base: 0,
+        offset: u32_at(end, 16) as u64,
+        size: u32_at(end, 12) as u64,
+        count: u16_at(end, 10) as u64,
+    };
+    // The central directory ends where the end record starts, unless there is a Zip64 record.
+    let mut directory_end = end_position;
+
+    if start >= ZIP64_LOCATOR_SIZE && u32_at(&tail, start - ZIP64_LOCATOR_SIZE) == ZIP64_LOCATOR {
+        let locator = &tail[start - ZIP64_LOCATOR_SIZE..start];
+        if u32_at(locator, 16) > 1 {
+            return Err(invalid(
+                "zip archives split across several disks are not supported",
+            ));
+        }
+        // The Zip64 record is normally just before the locator, but may be further back if it
+        // has extensible data, so trust the offset in the locator if a record is there.
+        let recorded = u64_at(locator, 8);
+        let expected = (end_position - ZIP64_LOCATOR_SIZE as u64)
+            .checked_sub(ZIP64_END_SIZE as u64)
+            .ok_or_else(|| invalid("invalid zip64 end of central directory record"))?;
+        let mut record = [0; ZIP64_END_SIZE];
+        let mut position = recorded;
+        if read_at(reader, position, &mut record).is_err() || u32_at(&record, 0) != ZIP64_END {
+            position = expected;
+            read_at(reader, position, &mut record)?;
+        }
+        if u32_at(&record, 0) != ZIP64_END {
+            return Err(invalid("invalid zip64 end of central directory record"));
+        }
+        if u32_at(&record, 16) != 0 || u32_at(&record, 20) != 0 {
+            return Err(invalid(
+                "zip archives split across several disks are not supported",
+            ));
+        }
+        directory.count = u64_at(&record, 32);
+        directory.size = u64_at(&record, 40);
+        directory.offset = u64_at(&record, 48);
+        directory_end = position;
+    }
+
+    let directory_start = directory_end
+        .checked_sub(directory.size)
+        .ok_or_else(|| invalid("zip central directory is larger than the archive"))?;
+    directory.base = directory_start
+        .checked_sub(directory.offset)
+        .ok_or_else(|| invalid("zip central directory is outside the archive"))?;
+    // Each entry takes at least the fixed part of its header.
+    if directory.count > directory.size / CENTRAL_HEADER_SIZE as u64 {
+        return Err(invalid(
+            "zip central directory is too small for its entries",
+        ));
+    }
+    Ok(directory)
+}
+
+/// Reads the bytes at the given position in the archive.
+fn read_at<R: Read + Seek>(reader: &mut R, position: u64, buf: &mut [u8]) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(position))?;
+    reader.read_exact(buf)
+}
+
+/// Reads the target of the uncompressed symbolic link of the given size whose local header is at
+/// the given position, for the entry with the given number.
+fn read_target<R: Read + Seek>(
+    reader: &mut R,
+    position: u64,
+    size: u64,
+    entry: u64,
+) -> io::Result<String> {
+    let error = || invalid(&format!("zip entry {}: invalid local header", entry));
+    let mut header = [0; LOCAL_HEADER_SIZE];
+    read_at(reader, position, &mut header).map_err(|_| error())?;
+    if u32_at(&header, 0) != LOCAL_HEADER {
+        return Err(error());
+    }
+    let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+    reader.seek(SeekFrom::Current(skip))?;
+    let mut target = vec![0; size as usize];
+    reader.read_exact(&mut target).map_err(|_| error())?;
+    Ok(String::from_utf8_lossy(&target).into_owned())
+}
+
+/// Returns the id and data of each field in the extra data of an entry, ignoring anything after
+/// a field which is cut short.
+fn extra_fields(mut extra: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        if extra.len() < 4 {
+            return None;
+        }
+        let (id, len) = (u16_at(extra, 0), u16_at(extra, 2) as usize);
+        let data = extra.get(4..4 + len)?;
+        extra = &extra[4 + len..];
+        Some((id, data))
+    })
+}
+
+/// Returns the time of the given MS-DOS date and time, taken to be in UTC, or `None` if the date
+/// is not set.
+fn dos_time(date: u16, time: u16) -> Option<SystemTime> {
+    if date == 0 {
+        return None;
+    }
+    let (year, month, day) = (
+        1980 + (date >> 9) as i64,
+        (date >> 5 & 0xf) as i64,
+        (date & 0x1f) as i64,
+    );
+    let (hours, minutes, seconds) = (
+        (time >> 11) as u64,
+        (time >> 5 & 0x3f) as u64,
+        (time & 0x1f) as u64 * 2,
+    );
+    let days = metadata::days_from_civil(year, month.clamp(1, 12), day.max(1));
+    let secs = days as u64 * 86400 + hours * 3600 + minutes * 60 + seconds;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Returns the character of the given byte in code page 437.
+fn cp437(byte: u8) -> char {
+    match byte {
+        0..0x80 => byte as char,
+        _ => CP437
+            .chars()
+            .nth(byte as usize - 0x80)
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
+}
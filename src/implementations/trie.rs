@@ -458,6 +458,26 @@ impl Node for Trie {
         }
     }
 
+    /// Returns the character consumed by the edge leading to this node, which is the last
+    /// character of its fragment, or `None` for the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::Trie;
+    /// use simple_tree::Node;
+    ///
+    /// let root = Trie::from(vec!["by"]);
+    /// assert!(root.edge_label().is_none());
+    ///
+    /// let b_node = root.children().next().unwrap();
+    /// let by_node = b_node.children().next().unwrap();
+    /// assert_eq!(format!("{}", by_node.edge_label().unwrap()), "y");
+    /// ```
+    fn edge_label(&self) -> Option<impl fmt::Display> {
+        self.fragment.chars().last()
+    }
+
     /// Returns an iterator over the [Trie] nodes whose
     /// associated with the node along with the count of the number of times that exact word has
     /// occurred (which may be 0).
@@ -2,8 +2,12 @@
 //!
 //! Simple tree provides a simple interface to build printable trees.
 
+pub mod edit_distance;
+pub mod explorer;
 pub mod implementations;
 pub mod node;
+pub mod render;
+pub mod visit;
 
 pub use implementations::DirTree;
 pub use node::Node;
@@ -1,18 +1,470 @@
 use std::env;
+use std::fs::File;
+use std::io::{
+    self, BufRead, BufReader, BufWriter, Cursor, IsTerminal, Read, Seek, SeekFrom, Write,
+};
+use std::process;
+use std::time::{Duration, UNIX_EPOCH};
 
 use simple_tree::DirTree;
+use simple_tree::implementations::DirTreeBuilder;
+use simple_tree::implementations::dir_tree::colors::LsColors;
+use simple_tree::implementations::dir_tree::paths::PathEntry;
+use simple_tree::implementations::dir_tree::snapshot::Snapshot;
+use simple_tree::implementations::dir_tree::watch::Watcher;
+use simple_tree::implementations::dir_tree::{
+    DEFAULT_TIME_FORMAT, DisplayOptions, EntryMetadata, SizeFormat, SortOrder,
+};
+
+/// The command-line options which apply to every root.
+#[derive(Default)]
+struct Options {
+    interactive: bool,
+    watch: bool,
+    snapshot: Option<String>,
+    diff: Option<String>,
+    duplicates: bool,
+    from_file: bool,
+    archive: bool,
+    separator: Option<String>,
+    show_hidden: bool,
+    gitignore: bool,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    one_filesystem: bool,
+    mounts: bool,
+    threads: usize,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    sort: SortOrder,
+    reverse: bool,
+    dirs_first: bool,
+    dirs_only: bool,
+    files_only: bool,
+    prune: bool,
+    sizes: bool,
+    size_format: Option<SizeFormat>,
+    du: bool,
+    inodes: bool,
+    permissions: bool,
+    links: bool,
+    user: bool,
+    group: bool,
+    time_format: Option<String>,
+    hash: bool,
+    hard_links: bool,
+    classify: bool,
+    // Whether to color names, or `None` to color them if writing to a terminal and `LS_COLORS` is
+    // set, like `tree`.
+    colors: Option<bool>,
+}
+
+impl Options {
+    /// Returns a [DirTreeBuilder] for the given root configured with these options.
+    fn builder(&self, root: &str) -> DirTreeBuilder {
+        let mut builder = DirTree::builder(root)
+            .show_hidden(self.show_hidden)
+            .gitignore(self.gitignore)
+            .max_depth(self.max_depth)
+            .follow_links(self.follow_links)
+            .one_filesystem(self.one_filesystem)
+            .mounts(self.mounts)
+            .threads(self.threads)
+            .sort(self.sort)
+            .reverse(self.reverse)
+            .dirs_first(self.dirs_first)
+            .dirs_only(self.dirs_only)
+            .files_only(self.files_only)
+            .prune(self.prune)
+            .hash(self.hash)
+            .hard_links(self.hard_links)
+            .display(self.display());
+        if let Some(separator) = &self.separator {
+            builder = builder.separator(separator);
+        }
+        // Like `tree`, patterns may be combined with `|`.
+        for pattern in self.include.iter().flat_map(|p| p.split('|')) {
+            builder = builder.include(pattern);
+        }
+        for pattern in self.exclude.iter().flat_map(|p| p.split('|')) {
+            builder = builder.exclude(pattern);
+        }
+        builder
+    }
+
+    /// Returns the [DisplayOptions] selected by these options.
+    fn display(&self) -> DisplayOptions {
+        // Like `tree`, human-readable sizes and `--du` imply `-s`.
+        let show_size = self.sizes || self.du || self.size_format.is_some();
+        DisplayOptions {
+            size: show_size.then(|| self.size_format.unwrap_or(SizeFormat::Bytes)),
+            du: self.du,
+            compressed: false,
+            inode: self.inodes,
+            permissions: self.permissions,
+            links: self.links,
+            user: self.user,
+            group: self.group,
+            modified: self.time_format.clone(),
+            hash: self.hash,
+            classify: self.classify,
+            colors: self.colors(),
+        }
+    }
+
+    /// Returns the colors selected by these options, if any.
+    fn colors(&self) -> Option<LsColors> {
+        match self.colors {
+            Some(true) => Some(LsColors::from_env().unwrap_or_default()),
+            Some(false) => None,
+            // The interactive view draws its own highlighting.
+            None if self.interactive || !io::stdout().is_terminal() => None,
+            None => LsColors::from_env(),
+        }
+    }
+}
 
 fn main() {
-    let mut was_arg = false;
-    for root in env::args().skip(1) {
-        was_arg = true;
+    let mut options = Options {
+        threads: 1,
+        ..Options::default()
+    };
+    let mut roots = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("simple-tree: missing argument to {}", name);
+                process::exit(2);
+            })
+        };
+        match arg.as_str() {
+            "-i" | "--interactive" => options.interactive = true,
+            "--watch" => options.watch = true,
+            "--snapshot" => options.snapshot = Some(value("--snapshot")),
+            "--diff" => options.diff = Some(value("--diff")),
+            "--duplicates" => options.duplicates = true,
+            "--fromfile" => options.from_file = true,
+            "--archive" => options.archive = true,
+            "--separator" => options.separator = Some(value("--separator")),
+            "-a" => options.show_hidden = true,
+            "-P" => options.include.push(value("-P")),
+            "-I" => options.exclude.push(value("-I")),
+            "--gitignore" => options.gitignore = true,
+            "-L" => options.max_depth = Some(number(&value("-L"), "-L")),
+            "-l" => options.follow_links = true,
+            "-x" => options.one_filesystem = true,
+            "--mounts" => options.mounts = true,
+            "-d" => options.dirs_only = true,
+            "--filesonly" => options.files_only = true,
+            "--prune" => options.prune = true,
+            "--threads" => options.threads = number(&value("--threads"), "--threads"),
+            "-s" => options.sizes = true,
+            "-h" => options.size_format = Some(SizeFormat::Iec),
+            "--si" => options.size_format = Some(SizeFormat::Si),
+            "--du" => options.du = true,
+            "--inodes" => options.inodes = true,
+            "-p" => options.permissions = true,
+            "--links" => options.links = true,
+            "-u" => options.user = true,
+            "-g" => options.group = true,
+            "--hash" => options.hash = true,
+            "--hardlinks" => options.hard_links = true,
+            "-F" => options.classify = true,
+            "-C" => options.colors = Some(true),
+            "-n" => options.colors = Some(false),
+            "-D" => {
+                let format = options.time_format.take();
+                options.time_format = format.or_else(|| Some(DEFAULT_TIME_FORMAT.to_string()));
+            }
+            // Like `tree`, a time format implies `-D`.
+            "--timefmt" => options.time_format = Some(value("--timefmt")),
+            "-v" => options.sort = SortOrder::Natural,
+            "-t" => options.sort = SortOrder::Modified,
+            "-U" => options.sort = SortOrder::Unsorted,
+            "-r" => options.reverse = true,
+            "--dirsfirst" => options.dirs_first = true,
+            _ if arg.starts_with("--sort=") => options.sort = sort_order(&arg["--sort=".len()..]),
+            _ => roots.push(arg),
+        }
+    }
+    if roots.is_empty() {
+        roots.push(String::from("."));
+    }
+
+    if options.watch {
+        if roots.len() > 1 {
+            eprintln!("simple-tree: --watch takes a single directory");
+            process::exit(2);
+        }
+        if let Err(e) = watch(&options.builder(&roots[0])) {
+            eprintln!("simple-tree: {}: {}", roots[0], e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if options.snapshot.is_some() || options.diff.is_some() {
+        if roots.len() > 1 {
+            eprintln!("simple-tree: --snapshot and --diff take a single directory");
+            process::exit(2);
+        }
+        match snapshot(&options, &roots[0]) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("simple-tree: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Like `tree`, keep going after errors, but report them and exit with a failure status.
+    let mut failed = false;
+    for root in roots {
+        let builder = options.builder(&root);
+        // Write the tree as it is read, unless it is needed in memory, or reading it on several
+        // threads, or the total sizes of directories are needed before they can be written.
+        let stream = !options.interactive
+            && !options.duplicates
+            && !options.from_file
+            && !options.archive
+            && options.threads == 1
+            && !options.du
+            && options.sort != SortOrder::Size;
+        if stream {
+            match builder.stream(&mut io::stdout().lock()) {
+                Ok(errors) => {
+                    for error in &errors {
+                        eprintln!("simple-tree: {}", error);
+                    }
+                    failed |= !errors.is_empty();
+                }
+                Err(e) => {
+                    eprintln!("simple-tree: {}: {}", root, e);
+                    failed = true;
+                }
+            }
+            continue;
+        }
+        let result = if options.from_file {
+            read_paths(&builder, &root).map(|tree| (tree, Vec::new()))
+        } else if options.archive {
+            read_archive(&options, &builder, &root).map(|tree| (tree, Vec::new()))
+        } else {
+            builder.build_tolerant()
+        };
+        let (tree, errors) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("simple-tree: {}: {}", root, e);
+                failed = true;
+                continue;
+            }
+        };
+        for error in &errors {
+            eprintln!("simple-tree: {}", error);
+        }
+        failed |= !errors.is_empty();
+        if options.duplicates {
+            let (duplicates, errors) = tree.duplicates();
+            for error in &errors {
+                eprintln!("simple-tree: {}", error);
+            }
+            failed |= !errors.is_empty();
+            if !duplicates.is_empty() {
+                println!("{}", duplicates);
+            }
+        } else if options.interactive {
+            if let Err(e) = explore(&tree) {
+                eprintln!("simple-tree: {}", e);
+                process::exit(1);
+            }
+        } else {
+            println!("{}", tree);
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Parses the value of a numeric option, exiting with an error if it is not a number.
+fn number(value: &str, name: &str) -> usize {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("simple-tree: invalid argument to {}: {}", name, value);
+        process::exit(2);
+    })
+}
+
+/// Parses the value of `--sort`, exiting with an error if it is not a known order.
+fn sort_order(value: &str) -> SortOrder {
+    match value {
+        "name" => SortOrder::Name,
+        "version" => SortOrder::Natural,
+        "nocase" => SortOrder::CaseInsensitive,
+        "extension" => SortOrder::Extension,
+        "mtime" => SortOrder::Modified,
+        "size" => SortOrder::Size,
+        "none" => SortOrder::Unsorted,
+        _ => {
+            eprintln!("simple-tree: invalid sort order: {}", value);
+            process::exit(2);
+        }
+    }
+}
+
+/// Builds a tree from the list of paths in the given file, or standard input if it is `-`, with
+/// one path on each line. Each path may be followed by a tab and its size in bytes, and then by a
+/// tab and the time it was last modified in seconds since the unix epoch.
+fn read_paths(builder: &DirTreeBuilder, file: &str) -> io::Result<DirTree> {
+    let input: Box<dyn BufRead> = match file {
+        "-" => Box::new(io::stdin().lock()),
+        _ => Box::new(BufReader::new(File::open(file)?)),
+    };
+    let mut entries = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let invalid = |field: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid {} on line {}", field, i + 1),
+            )
+        };
+        let mut fields = line.split('\t');
+        let path = fields.next().unwrap_or_default().to_string();
+        let size = match fields.next() {
+            Some(size) => size.parse().map_err(|_| invalid("size"))?,
+            None => 0,
+        };
+        let metadata = match fields.next() {
+            Some(time) => {
+                let secs = time.parse().map_err(|_| invalid("time"))?;
+                Some(EntryMetadata {
+                    modified: Some(UNIX_EPOCH + Duration::from_secs(secs)),
+                    ..EntryMetadata::default()
+                })
+            }
+            None => None,
+        };
+        entries.push(PathEntry {
+            path,
+            size,
+            metadata,
+            target: None,
+            compressed_size: None,
+            hard_link: None,
+        });
+    }
+    Ok(builder.from_paths(entries))
+}
+
+/// Builds a tree from the entries of the tar or zip archive in the given file, or standard input
+/// if it is `-`. Tar archives may be compressed with gzip, and the compressed sizes of the entries
+/// of zip archives are shown along with their sizes.
+fn read_archive(options: &Options, builder: &DirTreeBuilder, file: &str) -> io::Result<DirTree> {
+    // Zip archives are read from the end, so standard input is read into memory first.
+    let mut input: Box<dyn ReadSeek> = match file {
+        "-" => {
+            let mut bytes = Vec::new();
+            io::stdin().lock().read_to_end(&mut bytes)?;
+            Box::new(Cursor::new(bytes))
+        }
+        _ => Box::new(BufReader::new(File::open(file)?)),
+    };
+    // Zip archives start with a local header, or the end record if they are empty, unless they
+    // are self-extracting, in which case they are told apart by their names.
+    let mut magic = [0; 4];
+    let n = input.read(&mut magic)?;
+    input.seek(SeekFrom::Start(0))?;
+    let extension = file.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    let is_zip = matches!(&magic[..n], b"PK\x03\x04" | b"PK\x05\x06")
+        || matches!(extension.as_deref(), Some("zip" | "jar" | "whl"));
+    match is_zip {
+        true => builder
+            .clone()
+            .display(DisplayOptions {
+                compressed: true,
+                ..options.display()
+            })
+            .from_zip(input),
+        false => builder.from_tar(input),
+    }
+}
 
-        let tree = DirTree::new(root).unwrap();
-        println!("{}", tree);
+/// A reader which can also seek, as needed to read zip archives.
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Writes a snapshot of the given root to the file given by `--snapshot`, and compares it with
+/// the one in the file given by `--diff`, printing what differs as a tree. Returns whether the
+/// root was read without errors and matches the earlier snapshot.
+fn snapshot(options: &Options, root: &str) -> io::Result<bool> {
+    let (tree, errors) = options
+        .builder(root)
+        .build_tolerant()
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", root, e)))?;
+    for error in &errors {
+        eprintln!("simple-tree: {}", error);
+    }
+    let current = match options.hash {
+        true => Snapshot::hashed(&tree)?,
+        false => Snapshot::new(&tree),
+    };
+    if let Some(path) = &options.snapshot {
+        let file =
+            File::create(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        current.write(BufWriter::new(file))?;
     }
+    let Some(path) = &options.diff else {
+        return Ok(errors.is_empty());
+    };
+    let earlier = File::open(path)
+        .and_then(|file| Snapshot::read(BufReader::new(file)))
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    let diff = earlier.diff(&current);
+    if !diff.is_empty() {
+        println!("{}", diff.tree(root));
+    }
+    Ok(errors.is_empty() && diff.is_empty())
+}
 
-    if !was_arg {
-        let tree = DirTree::new(".").unwrap();
-        println!("{}", tree);
+/// Prints the tree again each time it changes, until interrupted. In a terminal, the screen is
+/// cleared before each frame, and changes since the last frame are highlighted.
+fn watch(builder: &DirTreeBuilder) -> io::Result<()> {
+    let mut watcher = Watcher::new(builder);
+    let terminal = io::stdout().is_terminal();
+    loop {
+        let frame = watcher.next_frame()?;
+        for error in &frame.errors {
+            eprintln!("simple-tree: {}", error);
+        }
+        let mut out = io::stdout().lock();
+        if terminal {
+            write!(out, "\x1b[H\x1b[2J")?;
+        }
+        writeln!(out, "{}", frame.render(terminal))?;
+        if !frame.removed().is_empty() {
+            writeln!(out, "({} removed)", frame.removed().len())?;
+        }
+        out.flush()?;
     }
 }
+
+/// Opens an interactive view of the given tree in the terminal.
+#[cfg(unix)]
+fn explore(tree: &DirTree) -> io::Result<()> {
+    use simple_tree::explorer::{Explorer, TerminalBackend};
+
+    let mut backend = TerminalBackend::new()?;
+    Explorer::new(tree).run(&mut backend)
+}
+
+#[cfg(not(unix))]
+fn explore(_tree: &DirTree) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "interactive mode requires a unix terminal",
+    ))
+}
@@ -1,9 +1,28 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
+use crate::render::{RenderOptions, RenderState, Rendered, print_line};
+use crate::visit::{Visit, Visitor, walk};
+
+/// The branch drawn before a child which is followed by more siblings.
+pub(crate) const BRANCH: &str = "├── ";
+/// The branch drawn before the last child of a node.
+pub(crate) const LAST_BRANCH: &str = "└── ";
+/// The prefix drawn below a child which is followed by more siblings.
+pub(crate) const PIPE: &str = "│   ";
+/// The prefix drawn below the last child of a node.
+pub(crate) const BLANK: &str = "    ";
+
+/// The position of a node relative to its siblings, which determines how its branch is drawn.
 #[derive(Debug, Clone, Copy)]
-enum SpecialStatus {
+pub enum SpecialStatus {
+    /// The node is a child which is followed by more siblings.
     None,
+    /// The node is the root of the tree being printed.
     Root,
+    /// The node is the last child of its parent.
     LastChild,
 }
 
@@ -44,6 +63,29 @@ pub trait Node {
     /// ```
     fn children(&self) -> impl Iterator<Item = &Self>;
 
+    /// Returns the label of the edge leading from this node's parent to this node, if there is one.
+    ///
+    /// Edge labels describe the relationship between a parent and child rather than the child
+    /// itself, such as which side of a binary tree the child is on. They are only drawn when
+    /// requested through [Node::render]. The default implementation returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::Node;
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(50);
+    /// root.insert(25);
+    ///
+    /// assert!(root.edge_label().is_none());
+    /// let child = root.children().next().unwrap();
+    /// assert_eq!(format!("{}", child.edge_label().unwrap()), "L");
+    /// ```
+    fn edge_label(&self) -> Option<impl fmt::Display> {
+        None::<&str>
+    }
+
     /// Returns the number of direct children of this node.
     ///
     /// # Examples
@@ -89,6 +131,162 @@ pub trait Node {
         self.children().map(|c| c.count_descendents() + 1).sum()
     }
 
+    /// Returns a hash of the subtree rooted at this node, computed Merkle-style from the value of
+    /// the node and the hashes of its children, in order.
+    ///
+    /// Structurally equal subtrees always have the same hash, wherever they appear and whatever
+    /// type of [Node] they are built from. Edge labels are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::{NodeBinaryUnbalanced, Trie};
+    /// use simple_tree::Node;
+    ///
+    /// let mut a = NodeBinaryUnbalanced::new("b");
+    /// a.insert("a");
+    /// let mut b = NodeBinaryUnbalanced::new("b");
+    /// b.insert("c");
+    ///
+    /// assert_ne!(a.structural_hash(), b.structural_hash());
+    /// b.insert("a");
+    /// assert_ne!(a.structural_hash(), b.structural_hash());
+    /// a.insert("c");
+    /// assert_eq!(a.structural_hash(), b.structural_hash());
+    /// ```
+    fn structural_hash(&self) -> u64 {
+        let child_hashes: Vec<u64> = self.children().map(|c| c.structural_hash()).collect();
+        combine_hash(&self.value().to_string(), &child_hashes)
+    }
+
+    /// Returns whether the subtree rooted at this node is equal to the one rooted at `other`, such
+    /// that both nodes have the same value and their children are pairwise structurally equal, in
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::Node;
+    ///
+    /// let mut a = NodeBinaryUnbalanced::new(2);
+    /// a.insert(1);
+    /// let mut b = NodeBinaryUnbalanced::new(2);
+    /// b.insert(3);
+    ///
+    /// assert!(!a.structurally_eq(&b));
+    /// assert!(a.structurally_eq(&a));
+    /// ```
+    fn structurally_eq<M: Node>(&self, other: &M) -> bool
+    where
+        Self: Sized,
+    {
+        self.value().to_string() == other.value().to_string()
+            && self.count_children() == other.count_children()
+            && self
+                .children()
+                .zip(other.children())
+                .all(|(a, b)| a.structurally_eq(b))
+    }
+
+    /// Returns whether the subtree rooted at this node is isomorphic to the one rooted at `other`,
+    /// such that both nodes have the same value and their children can be paired up into
+    /// isomorphic subtrees, in any order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::Node;
+    ///
+    /// let mut a = NodeBinaryUnbalanced::new(2);
+    /// a.insert(1);
+    /// a.insert(3);
+    /// let mut b = NodeBinaryUnbalanced::new(2);
+    /// b.insert(1);
+    ///
+    /// assert!(!a.is_isomorphic(&b));
+    /// b.insert(3);
+    /// assert!(a.is_isomorphic(&b));
+    /// ```
+    fn is_isomorphic<M: Node>(&self, other: &M) -> bool
+    where
+        Self: Sized,
+    {
+        isomorphic(&Unordered::new(self), &Unordered::new(other))
+    }
+
+    /// Traverses the tree rooted at this node depth-first, passing each node to the given
+    /// [Visitor]. Returns [Visit::Stop] if a hook stopped the traversal early, and
+    /// [Visit::Continue] otherwise.
+    ///
+    /// # Examples
+    ///
+    /// Closing tags are written as each node is left:
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::visit::{Visit, VisitContext, Visitor};
+    /// use simple_tree::Node;
+    ///
+    /// struct Xml(String);
+    ///
+    /// impl<N: Node> Visitor<N> for Xml {
+    ///     fn enter(&mut self, node: &N, _ctx: &VisitContext) -> Visit {
+    ///         self.0 += &format!("<n v=\"{}\">", node.value());
+    ///         Visit::Continue
+    ///     }
+    ///     fn leave(&mut self, _node: &N, _ctx: &VisitContext) -> Visit {
+    ///         self.0 += "</n>";
+    ///         Visit::Continue
+    ///     }
+    ///     fn leaf(&mut self, node: &N, _ctx: &VisitContext) -> Visit {
+    ///         self.0 += &format!("<n v=\"{}\"/>", node.value());
+    ///         Visit::Continue
+    ///     }
+    /// }
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(7);
+    /// root.insert(3);
+    /// root.insert(5);
+    /// root.insert(13);
+    ///
+    /// let mut xml = Xml(String::new());
+    /// assert_eq!(root.visit(&mut xml), Visit::Continue);
+    /// assert_eq!(xml.0, r#"<n v="7"><n v="3"><n v="5"/></n><n v="13"/></n>"#);
+    /// ```
+    ///
+    /// The traversal can be cut short:
+    ///
+    /// ```
+    /// use simple_tree::implementations::Trie;
+    /// use simple_tree::visit::{Visit, VisitContext, Visitor};
+    /// use simple_tree::Node;
+    ///
+    /// struct FindDeep(Option<String>);
+    ///
+    /// impl<N: Node> Visitor<N> for FindDeep {
+    ///     fn leaf(&mut self, node: &N, ctx: &VisitContext) -> Visit {
+    ///         if ctx.depth() >= 3 {
+    ///             self.0 = Some(node.value().to_string());
+    ///             return Visit::Stop;
+    ///         }
+    ///         Visit::Continue
+    ///     }
+    /// }
+    ///
+    /// let trie = Trie::from(vec!["a", "bc", "def", "ghij"]);
+    /// let mut finder = FindDeep(None);
+    /// assert_eq!(trie.visit(&mut finder), Visit::Stop);
+    /// assert_eq!(finder.0.as_deref(), Some("def 1"));
+    /// ```
+    fn visit<V: Visitor<Self>>(&self, visitor: &mut V) -> Visit
+    where
+        Self: Sized,
+    {
+        walk(self, visitor, &mut Vec::new(), 0)
+    }
+
     /// Formats a tree rooted at the given node and writes the result to the given formatter.
     ///
     /// # Examples
@@ -116,11 +314,48 @@ pub trait Node {
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut prefixes = vec![];
-        self.print_tree(f, &mut prefixes, SpecialStatus::Root)
+        let mut state = RenderState::default();
+        self.print_tree(f, &mut prefixes, SpecialStatus::Root, &mut state)
+    }
+
+    /// Returns a displayable tree rooted at the given node which is formatted according to the
+    /// given [RenderOptions], rather than the defaults used by [Self::fmt].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use simple_tree::implementations::Trie;
+    /// use simple_tree::render::{EdgeLabelStyle, RenderOptions};
+    /// use simple_tree::Node;
+    ///
+    /// let trie = Trie::from(vec!["hi", "ha", "he"]);
+    /// let options = RenderOptions {
+    ///     edge_labels: EdgeLabelStyle::Inline,
+    ///     ..RenderOptions::default()
+    /// };
+    ///
+    /// assert_eq!(format!("{}", trie.render(options)), "
+    /// └─[h]─ h 0
+    ///     ├─[a]─ ha 1
+    ///     ├─[e]─ he 1
+    ///     └─[i]─ hi 1");
+    /// ```
+    fn render(&self, options: RenderOptions) -> Rendered<'_, Self>
+    where
+        Self: Sized,
+    {
+        Rendered {
+            node: self,
+            options,
+        }
     }
 
-    /// A helper method of [Self::fmt] which recursively prints a node and its children to the given
-    /// formatter, given some existing prefixes and metadata about this node.
+    /// A helper method of [Self::fmt] and [Self::render] which prints a node and its descendents
+    /// to the given formatter, given some existing prefixes and metadata about this node. Each
+    /// child is printed by calling its own `print_tree`, so an implementation which overrides it
+    /// is used for every node of that type in the tree. The default output can also be produced
+    /// by a [Visitor] with [Self::visit], as shown for
+    /// [VisitContext::lasts](crate::visit::VisitContext::lasts).
     ///
     /// This should never be called directly.
     fn print_tree(
@@ -128,17 +363,16 @@ pub trait Node {
         f: &mut fmt::Formatter<'_>,
         prefixes: &mut Vec<&str>,
         status: SpecialStatus,
+        state: &mut RenderState,
     ) -> fmt::Result {
-        for pref in prefixes.iter() {
-            write!(f, "{}", pref)?;
+        if !print_line(f, self, prefixes, status, state)? {
+            return Ok(());
         }
-        let (s_prefix, c_prefix) = match status {
-            SpecialStatus::None => ("├── ", "│   "),
-            SpecialStatus::Root => ("", "\n"),
-            SpecialStatus::LastChild => ("└── ", "    "),
-        };
-        write!(f, "{}{}", s_prefix, self.value())?;
-        prefixes.push(c_prefix);
+        prefixes.push(match status {
+            SpecialStatus::None => PIPE,
+            SpecialStatus::Root => "\n",
+            SpecialStatus::LastChild => BLANK,
+        });
         let count = self.count_children();
         for (i, c) in self.children().enumerate() {
             let c_status = if i == count - 1 {
@@ -146,9 +380,67 @@ pub trait Node {
             } else {
                 SpecialStatus::None
             };
-            c.print_tree(f, prefixes, c_status)?;
+            c.print_tree(f, prefixes, c_status, state)?;
         }
         let _ = prefixes.pop();
         Ok(())
     }
 }
+
+/// Returns the structural hash of a node with the given value and children with the given
+/// structural hashes, in order.
+pub(crate) fn combine_hash(value: &str, child_hashes: &[u64]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    child_hashes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A node of a tree along with a hash of its subtree which ignores the order of children, so that
+/// isomorphic subtrees have equal hashes.
+struct Unordered {
+    value: String,
+    hash: u64,
+    children: Vec<Unordered>,
+}
+
+impl Unordered {
+    /// Returns the given tree with the hash of every subtree, computed once for each node.
+    fn new<N: Node>(node: &N) -> Self {
+        let value = node.value().to_string();
+        let children: Vec<Unordered> = node.children().map(Unordered::new).collect();
+        let mut hashes: Vec<u64> = children.iter().map(|c| c.hash).collect();
+        hashes.sort_unstable();
+        Unordered {
+            hash: combine_hash(&value, &hashes),
+            value,
+            children,
+        }
+    }
+}
+
+/// Returns whether the given trees are isomorphic, as for [Node::is_isomorphic].
+fn isomorphic(a: &Unordered, b: &Unordered) -> bool {
+    if a.hash != b.hash || a.value != b.value || a.children.len() != b.children.len() {
+        return false;
+    }
+    // Isomorphism is an equivalence relation, so greedily pairing each child with any unused
+    // isomorphic child of `b` finds a full pairing whenever one exists. Grouping the candidates
+    // by their hash keeps the comparisons to likely matches.
+    let mut candidates: HashMap<u64, Vec<&Unordered>> = HashMap::new();
+    for child in &b.children {
+        candidates.entry(child.hash).or_default().push(child);
+    }
+    a.children.iter().all(|child| {
+        let Some(group) = candidates.get_mut(&child.hash) else {
+            return false;
+        };
+        match group.iter().position(|c| isomorphic(child, c)) {
+            Some(i) => {
+                group.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    })
+}
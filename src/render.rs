@@ -0,0 +1,255 @@
+//! Options which control how a [Node] tree is rendered, beyond the default [Node::fmt] output.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::node::{BRANCH, LAST_BRANCH, Node, SpecialStatus, combine_hash};
+
+/// [EdgeLabelStyle] defines where the label returned by [Node::edge_label] is drawn relative to the
+/// branch and the value of a node.
+///
+/// # Examples
+///
+/// ```
+/// use simple_tree::implementations::NodeBinaryUnbalanced;
+/// use simple_tree::render::{EdgeLabelStyle, RenderOptions};
+/// use simple_tree::Node;
+///
+/// let mut root = NodeBinaryUnbalanced::new(7);
+/// root.insert(3);
+/// root.insert(13);
+///
+/// let render = |edge_labels| format!("{}", root.render(RenderOptions {
+///     edge_labels,
+///     ..RenderOptions::default()
+/// }));
+///
+/// assert_eq!(render(EdgeLabelStyle::Hidden), "7\n├── 3\n└── 13");
+/// assert_eq!(render(EdgeLabelStyle::Inline), "7\n├─[L]─ 3\n└─[R]─ 13");
+/// assert_eq!(render(EdgeLabelStyle::BeforeValue), "7\n├── [L] 3\n└── [R] 13");
+/// assert_eq!(render(EdgeLabelStyle::AfterValue), "7\n├── 3 [L]\n└── 13 [R]");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeLabelStyle {
+    /// Do not draw edge labels.
+    #[default]
+    Hidden,
+    /// Draw the label as part of the branch, as in `├─[L]─ 3`.
+    Inline,
+    /// Draw the label after the branch and before the value, as in `├── [L] 3`.
+    BeforeValue,
+    /// Draw the label after the value, as in `├── 3 [L]`.
+    AfterValue,
+}
+
+/// [RenderOptions] collects the settings used by [Node::render].
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Where to draw edge labels, if at all.
+    pub edge_labels: EdgeLabelStyle,
+    /// Whether to draw repeated subtrees only once.
+    ///
+    /// When set, the first occurrence of a subtree which appears again later is suffixed with an
+    /// id such as `#1`, and each later occurrence is drawn as a single line ending in
+    /// `(same as #1)`, without its descendents. Subtrees are compared with
+    /// [Node::structurally_eq]. Leaves are never collapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::env;
+    /// use std::fs;
+    /// use simple_tree::implementations::DirTree;
+    /// use simple_tree::render::RenderOptions;
+    /// use simple_tree::Node;
+    ///
+    /// let tmpdir = env::temp_dir().join("test-render-dedup");
+    /// for dir in ["a/vendor/lib", "b/vendor/lib", "c/vendor/lib", "c/src"] {
+    ///     fs::create_dir_all(tmpdir.join(dir)).unwrap();
+    /// }
+    /// let tree = DirTree::new(&tmpdir).unwrap();
+    /// fs::remove_dir_all(&tmpdir).unwrap();
+    ///
+    /// let options = RenderOptions {
+    ///     dedup: true,
+    ///     ..RenderOptions::default()
+    /// };
+    /// assert_eq!(format!("{}", tree.render(options)), format!(
+    /// "{}
+    /// ├── a
+    /// │   └── vendor #1
+    /// │       └── lib
+    /// ├── b
+    /// │   └── vendor (same as #1)
+    /// └── c
+    ///     ├── src
+    ///     └── vendor (same as #1)", tmpdir.display()));
+    /// ```
+    pub dedup: bool,
+}
+
+/// A tree which has been paired with [RenderOptions] so that it can be displayed.
+///
+/// This is returned by [Node::render].
+pub struct Rendered<'a, N: Node> {
+    pub(crate) node: &'a N,
+    pub(crate) options: RenderOptions,
+}
+
+impl<N: Node> fmt::Display for Rendered<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut prefixes = vec![];
+        let mut state = RenderState::new(self.node, self.options.clone());
+        self.node
+            .print_tree(f, &mut prefixes, SpecialStatus::Root, &mut state)
+    }
+}
+
+/// How a node is annotated when repeated subtrees are being collapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mark {
+    /// The node is drawn normally.
+    None,
+    /// The node is the first occurrence of a repeated subtree with the given id.
+    First(usize),
+    /// The node repeats the subtree with the given id, and its descendents, of which there are
+    /// the given number, are not drawn.
+    SameAs(usize, usize),
+}
+
+/// The state threaded through [Node::print_tree] while a single tree is being printed.
+#[derive(Debug, Default)]
+pub struct RenderState {
+    pub(crate) options: RenderOptions,
+    // The marks of every node in pre-order, or empty if subtrees are not being collapsed.
+    marks: Vec<Mark>,
+    // The pre-order index of the next node to be printed.
+    next: usize,
+}
+
+impl RenderState {
+    /// Returns the state for printing the tree rooted at the given node with the given options.
+    pub(crate) fn new<N: Node>(root: &N, options: RenderOptions) -> Self {
+        let marks = if options.dedup {
+            dedup_marks(root)
+        } else {
+            Vec::new()
+        };
+        RenderState {
+            options,
+            marks,
+            next: 0,
+        }
+    }
+
+    /// Returns the mark of the next node to be printed, and advances past it.
+    pub(crate) fn next_mark(&mut self) -> Mark {
+        let mark = self.marks.get(self.next).copied().unwrap_or(Mark::None);
+        self.next += 1;
+        mark
+    }
+
+    /// Skips over the given number of nodes which will not be printed.
+    pub(crate) fn skip(&mut self, count: usize) {
+        self.next += count;
+    }
+}
+
+/// Returns the [Mark] of every node in the tree rooted at the given node, in pre-order.
+fn dedup_marks<N: Node>(root: &N) -> Vec<Mark> {
+    let mut nodes = Vec::new();
+    let mut hashes = Vec::new();
+    let mut sizes = Vec::new();
+    collect(root, &mut nodes, &mut hashes, &mut sizes);
+
+    // Walk the tree in the order it will be printed, skipping the descendents of repeats just as
+    // printing will, so that only subtrees which are actually referenced are given ids.
+    let mut marks = vec![Mark::None; nodes.len()];
+    let mut first: HashMap<u64, usize> = HashMap::new();
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    let mut i = 0;
+    while i < nodes.len() {
+        if sizes[i] == 0 {
+            i += 1;
+            continue;
+        }
+        match first.get(&hashes[i]) {
+            Some(&original) if nodes[original].structurally_eq(nodes[i]) => {
+                let next_id = ids.len() + 1;
+                let id = *ids.entry(original).or_insert(next_id);
+                marks[original] = Mark::First(id);
+                marks[i] = Mark::SameAs(id, sizes[i]);
+                i += sizes[i] + 1;
+            }
+            Some(_) => i += 1,
+            None => {
+                first.insert(hashes[i], i);
+                i += 1;
+            }
+        }
+    }
+    marks
+}
+
+/// Appends the given node and its descendents to `nodes` in pre-order, along with their
+/// structural hashes and number of descendents, and returns the hash of the node.
+fn collect<'a, N: Node>(
+    node: &'a N,
+    nodes: &mut Vec<&'a N>,
+    hashes: &mut Vec<u64>,
+    sizes: &mut Vec<usize>,
+) -> u64 {
+    let index = nodes.len();
+    nodes.push(node);
+    hashes.push(0);
+    sizes.push(0);
+    let child_hashes: Vec<u64> = node
+        .children()
+        .map(|c| collect(c, nodes, hashes, sizes))
+        .collect();
+    hashes[index] = combine_hash(&node.value().to_string(), &child_hashes);
+    sizes[index] = nodes.len() - index - 1;
+    hashes[index]
+}
+
+/// Writes the line for the given node for [Node::print_tree], after the given prefixes and the
+/// branch for the given status, along with any mark from collapsing repeated subtrees. Returns
+/// whether the children of the node should be printed, which they are not if it repeats an
+/// earlier subtree.
+pub(crate) fn print_line<N: Node + ?Sized>(
+    f: &mut fmt::Formatter<'_>,
+    node: &N,
+    prefixes: &[&str],
+    status: SpecialStatus,
+    state: &mut RenderState,
+) -> Result<bool, fmt::Error> {
+    for pref in prefixes {
+        write!(f, "{}", pref)?;
+    }
+    let branch = match status {
+        SpecialStatus::None => BRANCH,
+        SpecialStatus::Root => "",
+        SpecialStatus::LastChild => LAST_BRANCH,
+    };
+    match (state.options.edge_labels, node.edge_label()) {
+        (EdgeLabelStyle::Inline, Some(label)) if !branch.is_empty() => {
+            let corner: String = branch.chars().take(1).collect();
+            write!(f, "{}─[{}]─ {}", corner, label, node.value())?
+        }
+        (EdgeLabelStyle::BeforeValue, Some(label)) => {
+            write!(f, "{}[{}] {}", branch, label, node.value())?
+        }
+        (EdgeLabelStyle::AfterValue, Some(label)) => {
+            write!(f, "{}{} [{}]", branch, node.value(), label)?
+        }
+        _ => write!(f, "{}{}", branch, node.value())?,
+    }
+    match state.next_mark() {
+        Mark::None => Ok(true),
+        Mark::First(id) => write!(f, " #{}", id).map(|()| true),
+        Mark::SameAs(id, size) => {
+            state.skip(size);
+            write!(f, " (same as #{})", id).map(|()| false)
+        }
+    }
+}
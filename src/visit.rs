@@ -0,0 +1,156 @@
+//! Depth-first traversal of a [Node] tree with explicit events for entering and leaving each node.
+//!
+//! See [Node::visit].
+
+use crate::node::Node;
+
+/// [Visit] tells [Node::visit] how to continue after a [Visitor] hook returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    /// Continue the traversal normally.
+    Continue,
+    /// Do not visit the children of the node just entered, but still leave it. When returned from
+    /// any other hook, this is the same as [Visit::Continue].
+    SkipChildren,
+    /// End the traversal immediately, without leaving any of the nodes which have been entered.
+    Stop,
+}
+
+/// The position of a node within the traversal, passed to each [Visitor] hook.
+#[derive(Debug, Clone, Copy)]
+pub struct VisitContext<'a> {
+    // For each node on the path from the root (exclusive) to this node (inclusive), whether that
+    // node is the last child of its parent.
+    lasts: &'a [bool],
+    index: usize,
+}
+
+impl VisitContext<'_> {
+    /// Returns the depth of the node, where the root of the traversal has depth `0`.
+    pub fn depth(&self) -> usize {
+        self.lasts.len()
+    }
+
+    /// Returns whether the node is the root of the traversal.
+    pub fn is_root(&self) -> bool {
+        self.lasts.is_empty()
+    }
+
+    /// Returns the index of the node among its siblings, or `0` for the root.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns whether the node is the last child of its parent. The root is considered last.
+    pub fn is_last(&self) -> bool {
+        self.lasts.last().copied().unwrap_or(true)
+    }
+
+    /// Returns, for each node on the path from the child of the root down to this node, whether
+    /// that node is the last child of its parent. This is empty for the root.
+    ///
+    /// This is all that is needed to draw the branches in front of a node, so the default
+    /// rendering of a tree can be reproduced by a [Visitor]:
+    ///
+    /// ```
+    /// use simple_tree::implementations::NodeBinaryUnbalanced;
+    /// use simple_tree::visit::{Visit, VisitContext, Visitor};
+    /// use simple_tree::Node;
+    ///
+    /// struct Lines(Vec<String>);
+    ///
+    /// impl Lines {
+    ///     fn line<N: Node>(&mut self, node: &N, ctx: &VisitContext) -> Visit {
+    ///         let mut line = String::new();
+    ///         if let Some((&last, ancestors)) = ctx.lasts().split_last() {
+    ///             for &ancestor in ancestors {
+    ///                 line += if ancestor { "    " } else { "│   " };
+    ///             }
+    ///             line += if last { "└── " } else { "├── " };
+    ///         }
+    ///         self.0.push(line + &node.value().to_string());
+    ///         Visit::Continue
+    ///     }
+    /// }
+    ///
+    /// impl<N: Node> Visitor<N> for Lines {
+    ///     fn enter(&mut self, node: &N, ctx: &VisitContext) -> Visit {
+    ///         self.line(node, ctx)
+    ///     }
+    ///
+    ///     fn leaf(&mut self, node: &N, ctx: &VisitContext) -> Visit {
+    ///         self.line(node, ctx)
+    ///     }
+    /// }
+    ///
+    /// let mut root = NodeBinaryUnbalanced::new(7);
+    /// for value in [3, 13, 2, 5, 11, 15, 1] {
+    ///     root.insert(value);
+    /// }
+    ///
+    /// let mut lines = Lines(Vec::new());
+    /// root.visit(&mut lines);
+    /// assert_eq!(lines.0.join("\n"), format!("{}", root));
+    /// ```
+    pub fn lasts(&self) -> &[bool] {
+        self.lasts
+    }
+}
+
+/// [Visitor] receives events from [Node::visit] as a tree is traversed depth-first.
+///
+/// A node with children is entered, then its children are visited, then it is left. A node without
+/// children is passed to [Visitor::leaf] instead. Every hook does nothing by default.
+pub trait Visitor<N: ?Sized> {
+    /// Called before the children of a node which has children are visited.
+    fn enter(&mut self, _node: &N, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called after the children of a node which has children have been visited.
+    fn leave(&mut self, _node: &N, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called for a node which has no children.
+    fn leaf(&mut self, _node: &N, _ctx: &VisitContext) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// Visits the given node and its descendents, where `lasts` describes the position of the node,
+/// returning [Visit::Stop] if the traversal was stopped early.
+pub(crate) fn walk<N, V>(node: &N, visitor: &mut V, lasts: &mut Vec<bool>, index: usize) -> Visit
+where
+    N: Node + ?Sized,
+    V: Visitor<N> + ?Sized,
+{
+    let count = node.count_children();
+    if count == 0 {
+        let ctx = VisitContext { lasts, index };
+        return match visitor.leaf(node, &ctx) {
+            Visit::Stop => Visit::Stop,
+            _ => Visit::Continue,
+        };
+    }
+    let ctx = VisitContext { lasts, index };
+    match visitor.enter(node, &ctx) {
+        Visit::Stop => return Visit::Stop,
+        Visit::SkipChildren => (),
+        Visit::Continue => {
+            for (i, child) in node.children().enumerate() {
+                lasts.push(i == count - 1);
+                let flow = walk(child, visitor, lasts, i);
+                lasts.pop();
+                if flow == Visit::Stop {
+                    return Visit::Stop;
+                }
+            }
+        }
+    }
+    let ctx = VisitContext { lasts, index };
+    match visitor.leave(node, &ctx) {
+        Visit::Stop => Visit::Stop,
+        _ => Visit::Continue,
+    }
+}